@@ -0,0 +1,181 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers shared by the individual encoding modules: rectangle framing and
+//! pixel packing/unpacking against an arbitrary [`PixelFormat`].
+
+use crate::PixelFormat;
+use bytes::{Buf, BufMut, BytesMut};
+use std::io;
+
+/// Number of bytes per pixel in the RGBA buffers this crate operates on.
+pub const RGBA_BPP: usize = 4;
+
+/// Writes an RFB rectangle header: x, y, width, height, then the signed
+/// encoding-type, as defined by the `FramebufferUpdate` message format.
+pub fn write_rectangle_header(
+    buf: &mut BytesMut,
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+    encoding_type: i32,
+) {
+    buf.put_u16(x);
+    buf.put_u16(y);
+    buf.put_u16(width);
+    buf.put_u16(height);
+    buf.put_i32(encoding_type);
+}
+
+/// Packs an 8-bit-per-channel RGB triple into a `pf`-shaped pixel value.
+#[must_use]
+pub fn pack_pixel(pf: &PixelFormat, r: u8, g: u8, b: u8) -> u32 {
+    let scale = |component: u8, max: u16| -> u32 {
+        if max == 0 {
+            0
+        } else {
+            u32::from(component) * u32::from(max) / 255
+        }
+    };
+
+    (scale(r, pf.red_max) << pf.red_shift)
+        | (scale(g, pf.green_max) << pf.green_shift)
+        | (scale(b, pf.blue_max) << pf.blue_shift)
+}
+
+/// Writes a packed pixel value as `pf.bits_per_pixel / 8` bytes, honoring
+/// `pf.big_endian_flag`.
+pub fn write_pixel(buf: &mut BytesMut, pf: &PixelFormat, pixel: u32) {
+    let bytes = pixel.to_be_bytes();
+    let n = usize::from(pf.bits_per_pixel / 8);
+    if pf.big_endian_flag == 0 {
+        for byte in bytes[4 - n..].iter().rev() {
+            buf.put_u8(*byte);
+        }
+    } else {
+        buf.put_slice(&bytes[4 - n..]);
+    }
+}
+
+/// Inverse of [`pack_pixel`]: recovers an 8-bit-per-channel RGB triple from a
+/// `pf`-shaped pixel value.
+#[must_use]
+pub fn unpack_pixel(pf: &PixelFormat, pixel: u32) -> (u8, u8, u8) {
+    #[allow(clippy::cast_possible_truncation)]
+    let unscale = |value: u32, max: u16| -> u8 {
+        if max == 0 {
+            0
+        } else {
+            (value * 255 / u32::from(max)) as u8
+        }
+    };
+
+    let r = unscale((pixel >> pf.red_shift) & u32::from(pf.red_max), pf.red_max);
+    let g = unscale(
+        (pixel >> pf.green_shift) & u32::from(pf.green_max),
+        pf.green_max,
+    );
+    let b = unscale(
+        (pixel >> pf.blue_shift) & u32::from(pf.blue_max),
+        pf.blue_max,
+    );
+    (r, g, b)
+}
+
+/// Reads a `pf.bits_per_pixel / 8`-byte pixel value from `buf`, honoring
+/// `pf.big_endian_flag`.
+///
+/// # Errors
+///
+/// Returns `Err(io::Error)` if `buf` does not hold enough bytes for one pixel.
+pub fn read_pixel(buf: &mut BytesMut, pf: &PixelFormat) -> io::Result<u32> {
+    let n = usize::from(pf.bits_per_pixel / 8);
+    if buf.len() < n {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "not enough bytes for pixel",
+        ));
+    }
+
+    let mut value: u32 = 0;
+    if pf.big_endian_flag == 0 {
+        for i in 0..n {
+            value |= u32::from(buf[i]) << (8 * i);
+        }
+    } else {
+        for i in 0..n {
+            value = (value << 8) | u32::from(buf[i]);
+        }
+    }
+    buf.advance(n);
+    Ok(value)
+}
+
+/// Returns `true` if two RGBA pixels (4 bytes each, at offset 0) are
+/// byte-for-byte identical.
+#[must_use]
+pub fn pixels_equal(a: &[u8], b: &[u8]) -> bool {
+    a[..RGBA_BPP] == b[..RGBA_BPP]
+}
+
+/// Returns the RGBA pixel at `(x, y)` in a `width`-wide buffer as a 4-byte slice.
+#[must_use]
+pub fn pixel_at(data: &[u8], width: u16, x: u16, y: u16) -> &[u8] {
+    let offset = (usize::from(y) * usize::from(width) + usize::from(x)) * RGBA_BPP;
+    &data[offset..offset + RGBA_BPP]
+}
+
+/// Writes `len` using the Tight/`ZRLE`-style variable-length "compact
+/// length" encoding: 7 bits per byte, continuation in the high bit.
+pub fn write_compact_length(buf: &mut BytesMut, mut len: usize) {
+    loop {
+        #[allow(clippy::cast_possible_truncation)]
+        let mut byte = (len & 0x7f) as u8;
+        len >>= 7;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        buf.put_u8(byte);
+        if len == 0 {
+            break;
+        }
+    }
+}
+
+/// Reads a compact length previously written by [`write_compact_length`].
+///
+/// # Errors
+///
+/// Returns `Err(io::Error)` if `buf` runs out of bytes before the
+/// continuation bit clears.
+pub fn read_compact_length(buf: &mut BytesMut) -> io::Result<usize> {
+    let mut len = 0usize;
+    let mut shift = 0u32;
+    loop {
+        if buf.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "not enough bytes for compact length",
+            ));
+        }
+        let byte = buf.get_u8();
+        len |= usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Ok(len)
+}
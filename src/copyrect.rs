@@ -0,0 +1,164 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! CopyRect: tells the client to copy pixels it already has from one part
+//! of its own framebuffer to another, instead of resending them.
+//!
+//! CopyRect's body is just a source x/y pair, which doesn't fit the
+//! `Encoding::encode(data, width, height, quality, compression)` contract
+//! (there is no "previous frame" or "dirty rectangle" in that signature).
+//! [`CopyRectEncoder`] is a standalone detector callers drive directly,
+//! falling back to a pixel encoding when no matching region is found.
+
+use crate::common::RGBA_BPP;
+use bytes::{BufMut, BytesMut};
+
+/// A rectangle that changed between two framebuffers, in current-frame
+/// coordinates.
+#[derive(Debug, Clone, Copy)]
+pub struct DirtyRect {
+    /// X coordinate of the dirty rectangle's top-left corner.
+    pub x: u16,
+    /// Y coordinate of the dirty rectangle's top-left corner.
+    pub y: u16,
+    /// Width of the dirty rectangle.
+    pub width: u16,
+    /// Height of the dirty rectangle.
+    pub height: u16,
+}
+
+/// A confirmed CopyRect match: the dirty region is a byte-for-byte
+/// translation of an already-sent region at `(src_x, src_y)`.
+#[derive(Debug, Clone, Copy)]
+pub struct CopyRectMatch {
+    /// X coordinate of the previously-sent source region.
+    pub src_x: u16,
+    /// Y coordinate of the previously-sent source region.
+    pub src_y: u16,
+}
+
+impl CopyRectMatch {
+    /// Serializes the 4-byte CopyRect body: source x, then source y.
+    #[must_use]
+    pub fn encode(&self) -> BytesMut {
+        let mut buf = BytesMut::with_capacity(4);
+        buf.put_u16(self.src_x);
+        buf.put_u16(self.src_y);
+        buf
+    }
+}
+
+/// Detects CopyRect opportunities by searching a bounded window of
+/// candidate translations between a previous and current framebuffer.
+pub struct CopyRectEncoder<'a> {
+    previous: &'a [u8],
+    current: &'a [u8],
+    width: u16,
+    height: u16,
+    /// Maximum horizontal/vertical shift to search, in pixels.
+    search_radius: u16,
+}
+
+impl<'a> CopyRectEncoder<'a> {
+    /// Creates a detector over the given previous/current RGBA framebuffers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `previous` and `current` are not both
+    /// `width * height * 4` bytes.
+    #[must_use]
+    pub fn new(previous: &'a [u8], current: &'a [u8], width: u16, height: u16) -> Self {
+        let expected = usize::from(width) * usize::from(height) * RGBA_BPP;
+        assert_eq!(previous.len(), expected, "previous framebuffer size mismatch");
+        assert_eq!(current.len(), expected, "current framebuffer size mismatch");
+        Self {
+            previous,
+            current,
+            width,
+            height,
+            search_radius: 64,
+        }
+    }
+
+    /// Overrides the default search radius (64 pixels in each direction).
+    #[must_use]
+    pub fn with_search_radius(mut self, radius: u16) -> Self {
+        self.search_radius = radius;
+        self
+    }
+
+    fn pixel<'b>(buf: &'b [u8], width: u16, x: i32, y: i32) -> Option<&'b [u8]> {
+        if x < 0 || y < 0 || x >= i32::from(width) {
+            return None;
+        }
+        let offset = (y as usize * usize::from(width) + x as usize) * RGBA_BPP;
+        buf.get(offset..offset + RGBA_BPP)
+    }
+
+    /// Checks whether `dirty` is a translation by `(dx, dy)` of the
+    /// previous framebuffer, i.e. every pixel in the dirty region equals
+    /// `previous[(x - dx, y - dy)]`.
+    fn matches_shift(&self, dirty: DirtyRect, dx: i32, dy: i32) -> bool {
+        for y in dirty.y..dirty.y + dirty.height {
+            for x in dirty.x..dirty.x + dirty.width {
+                let src_x = i32::from(x) - dx;
+                let src_y = i32::from(y) - dy;
+                let Some(src) = Self::pixel(self.previous, self.width, src_x, src_y) else {
+                    return false;
+                };
+                let Some(dst) = Self::pixel(self.current, self.width, i32::from(x), i32::from(y))
+                else {
+                    return false;
+                };
+                if src != dst {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Searches candidate `(dx, dy)` shifts within the configured search
+    /// radius and returns the first confirmed byte-for-byte translation of
+    /// `dirty`, or `None` if the caller should fall back to a pixel
+    /// encoding instead.
+    #[must_use]
+    pub fn detect(&self, dirty: DirtyRect) -> Option<CopyRectMatch> {
+        let radius = i32::from(self.search_radius);
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let src_x = i32::from(dirty.x) - dx;
+                let src_y = i32::from(dirty.y) - dy;
+                if src_x < 0
+                    || src_y < 0
+                    || src_x + i32::from(dirty.width) > i32::from(self.width)
+                    || src_y + i32::from(dirty.height) > i32::from(self.height)
+                {
+                    continue;
+                }
+                if self.matches_shift(dirty, dx, dy) {
+                    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+                    return Some(CopyRectMatch {
+                        src_x: src_x as u16,
+                        src_y: src_y as u16,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
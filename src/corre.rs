@@ -0,0 +1,217 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compact RRE (`CoRRE`): RRE restricted to 255x255 tiles with byte-sized
+//! subrectangle coordinates, trading range for a smaller header.
+
+use crate::common::{self, pixels_equal, RGBA_BPP};
+use crate::{Decoder, Encoding, PixelFormat};
+use bytes::{Buf, BufMut, BytesMut};
+use std::io;
+
+/// Maximum tile extent `CoRRE` can address with its single-byte coordinates.
+const TILE_SIZE: u16 = 255;
+
+/// Compact RRE encoding: RRE tiled into 255x255 blocks with `u8` coordinates.
+pub struct CorRreEncoding;
+
+fn most_common_pixel(data: &[u8], width: u16, x0: u16, y0: u16, w: u16, h: u16) -> [u8; RGBA_BPP] {
+    let mut counts = std::collections::HashMap::new();
+    for y in y0..y0 + h {
+        for x in x0..x0 + w {
+            let px = common::pixel_at(data, width, x, y);
+            let key = [px[0], px[1], px[2], px[3]];
+            *counts.entry(key).or_insert(0usize) += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map_or([0, 0, 0, 0], |(pixel, _)| pixel)
+}
+
+fn encode_tile(buf: &mut BytesMut, pf: &PixelFormat, data: &[u8], width: u16, x0: u16, y0: u16, w: u16, h: u16) {
+    let background = most_common_pixel(data, width, x0, y0, w, h);
+    let mut runs = Vec::new();
+    for y in y0..y0 + h {
+        let mut x = x0;
+        while x < x0 + w {
+            let px = common::pixel_at(data, width, x, y);
+            if pixels_equal(px, &background) {
+                x += 1;
+                continue;
+            }
+            let run_pixel = [px[0], px[1], px[2], px[3]];
+            let start = x;
+            while x < x0 + w && pixels_equal(common::pixel_at(data, width, x, y), &run_pixel) {
+                x += 1;
+            }
+            runs.push((start - x0, y - y0, x - start, run_pixel));
+        }
+    }
+
+    buf.put_u32(u32::try_from(runs.len()).unwrap_or(u32::MAX));
+    let bg_pixel = common::pack_pixel(pf, background[0], background[1], background[2]);
+    common::write_pixel(buf, pf, bg_pixel);
+
+    for (x, y, w, pixel) in runs {
+        let packed = common::pack_pixel(pf, pixel[0], pixel[1], pixel[2]);
+        common::write_pixel(buf, pf, packed);
+        #[allow(clippy::cast_possible_truncation)]
+        buf.put_u8(x as u8);
+        #[allow(clippy::cast_possible_truncation)]
+        buf.put_u8(y as u8);
+        #[allow(clippy::cast_possible_truncation)]
+        buf.put_u8(w as u8);
+        buf.put_u8(1);
+    }
+}
+
+impl Encoding for CorRreEncoding {
+    fn encode(
+        &self,
+        data: &[u8],
+        width: u16,
+        height: u16,
+        _quality: u8,
+        _compression: u8,
+    ) -> BytesMut {
+        let pf = PixelFormat::rgba32();
+        let mut buf = BytesMut::new();
+
+        let mut y0 = 0;
+        while y0 < height {
+            let h = TILE_SIZE.min(height - y0);
+            let mut x0 = 0;
+            while x0 < width {
+                let w = TILE_SIZE.min(width - x0);
+                encode_tile(&mut buf, &pf, data, width, x0, y0, w, h);
+                x0 += w;
+            }
+            y0 += h;
+        }
+        buf
+    }
+}
+
+impl Decoder for CorRreEncoding {
+    fn decode(
+        &self,
+        data: &mut BytesMut,
+        width: u16,
+        height: u16,
+        pf: &PixelFormat,
+    ) -> io::Result<Vec<u8>> {
+        let mut out = vec![0u8; usize::from(width) * usize::from(height) * RGBA_BPP];
+
+        let mut y0 = 0;
+        while y0 < height {
+            let h = TILE_SIZE.min(height - y0);
+            let mut x0 = 0;
+            while x0 < width {
+                let w = TILE_SIZE.min(width - x0);
+                decode_tile(data, pf, &mut out, width, x0, y0, w, h)?;
+                x0 += w;
+            }
+            y0 += h;
+        }
+        Ok(out)
+    }
+}
+
+fn decode_tile(
+    data: &mut BytesMut,
+    pf: &PixelFormat,
+    out: &mut [u8],
+    width: u16,
+    x0: u16,
+    y0: u16,
+    tile_w: u16,
+    tile_h: u16,
+) -> io::Result<()> {
+    if data.len() < 4 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "not enough bytes for CoRRE subrectangle count",
+        ));
+    }
+    let count = data.get_u32();
+    let background = common::read_pixel(data, pf)?;
+    let (br, bg, bb) = common::unpack_pixel(pf, background);
+
+    for y in y0..y0 + tile_h {
+        for x in x0..x0 + tile_w {
+            let offset = (usize::from(y) * usize::from(width) + usize::from(x)) * RGBA_BPP;
+            out[offset..offset + RGBA_BPP].copy_from_slice(&[br, bg, bb, 255]);
+        }
+    }
+
+    for _ in 0..count {
+        let pixel = common::read_pixel(data, pf)?;
+        let (r, g, b) = common::unpack_pixel(pf, pixel);
+        if data.len() < 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "not enough bytes for CoRRE subrectangle header",
+            ));
+        }
+        let x = u16::from(data.get_u8());
+        let y = u16::from(data.get_u8());
+        let w = u16::from(data.get_u8());
+        let h = u16::from(data.get_u8());
+        if x + w > tile_w || y + h > tile_h {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "CoRRE subrectangle exceeds tile bounds",
+            ));
+        }
+        for row in (y0 + y)..(y0 + y + h) {
+            for col in (x0 + x)..(x0 + x + w) {
+                let offset = (usize::from(row) * usize::from(width) + usize::from(col)) * RGBA_BPP;
+                out[offset..offset + RGBA_BPP].copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_encode_and_decode() {
+        let pf = PixelFormat::rgba32();
+        let (width, height) = (4, 4);
+        let background = [10, 20, 30, 255];
+        let foreground = [200, 100, 50, 255];
+
+        let mut data = Vec::with_capacity(usize::from(width) * usize::from(height) * RGBA_BPP);
+        for y in 0..height {
+            for x in 0..width {
+                let px = if (1..3).contains(&x) && y == 2 {
+                    foreground
+                } else {
+                    background
+                };
+                data.extend_from_slice(&px);
+            }
+        }
+
+        let mut encoded = CorRreEncoding.encode(&data, width, height, 0, 0);
+        let decoded = CorRreEncoding.decode(&mut encoded, width, height, &pf).unwrap();
+
+        assert_eq!(decoded, data);
+    }
+}
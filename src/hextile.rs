@@ -0,0 +1,305 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hextile encoding: the framebuffer is split into 16x16 tiles, each sent
+//! either raw or as a background/foreground color plus colored subrects.
+
+use crate::common::{self, pixels_equal, RGBA_BPP};
+use crate::{
+    Decoder, Encoding, PixelFormat, HEXTILE_ANY_SUBRECTS, HEXTILE_BACKGROUND_SPECIFIED,
+    HEXTILE_FOREGROUND_SPECIFIED, HEXTILE_RAW, HEXTILE_SUBRECTS_COLOURED,
+};
+use bytes::{Buf, BufMut, BytesMut};
+use std::collections::HashMap;
+use std::io;
+
+/// Tile edge length used by the Hextile encoding.
+const TILE_SIZE: u16 = 16;
+
+/// Hextile encoding: 16x16 tiles, each raw or background/foreground + subrects.
+pub struct HextileEncoding;
+
+fn unique_colors(data: &[u8], width: u16, x0: u16, y0: u16, w: u16, h: u16) -> HashMap<[u8; RGBA_BPP], usize> {
+    let mut counts = HashMap::new();
+    for y in y0..y0 + h {
+        for x in x0..x0 + w {
+            let px = common::pixel_at(data, width, x, y);
+            let key = [px[0], px[1], px[2], px[3]];
+            *counts.entry(key).or_insert(0usize) += 1;
+        }
+    }
+    counts
+}
+
+fn encode_tile(buf: &mut BytesMut, pf: &PixelFormat, data: &[u8], width: u16, x0: u16, y0: u16, w: u16, h: u16, prev_bg: Option<[u8; RGBA_BPP]>) -> Option<[u8; RGBA_BPP]> {
+    let colors = unique_colors(data, width, x0, y0, w, h);
+
+    // More than two colors: give up and send the tile raw.
+    if colors.len() > 2 {
+        buf.put_u8(HEXTILE_RAW);
+        for y in y0..y0 + h {
+            for x in x0..x0 + w {
+                let px = common::pixel_at(data, width, x, y);
+                let packed = common::pack_pixel(pf, px[0], px[1], px[2]);
+                common::write_pixel(buf, pf, packed);
+            }
+        }
+        return None;
+    }
+
+    let background = *colors
+        .iter()
+        .max_by_key(|(_, count)| **count)
+        .map_or(&[0, 0, 0, 0], |(pixel, _)| pixel);
+    let foreground = colors.keys().find(|c| **c != background).copied();
+
+    let mut flags = 0u8;
+    if Some(background) != prev_bg {
+        flags |= HEXTILE_BACKGROUND_SPECIFIED;
+    }
+    if foreground.is_some() {
+        flags |= HEXTILE_FOREGROUND_SPECIFIED;
+    }
+
+    let mut subrects = Vec::new();
+    if let Some(fg) = foreground {
+        let mut y = y0;
+        while y < y0 + h {
+            let mut x = x0;
+            while x < x0 + w {
+                let px = common::pixel_at(data, width, x, y);
+                if pixels_equal(px, &fg) {
+                    let start = x;
+                    while x < x0 + w && pixels_equal(common::pixel_at(data, width, x, y), &fg) {
+                        x += 1;
+                    }
+                    subrects.push((start - x0, y - y0, x - start));
+                } else {
+                    x += 1;
+                }
+            }
+            y += 1;
+        }
+        if !subrects.is_empty() {
+            flags |= HEXTILE_ANY_SUBRECTS;
+        }
+    }
+
+    buf.put_u8(flags);
+    if flags & HEXTILE_BACKGROUND_SPECIFIED != 0 {
+        let packed = common::pack_pixel(pf, background[0], background[1], background[2]);
+        common::write_pixel(buf, pf, packed);
+    }
+    if flags & HEXTILE_FOREGROUND_SPECIFIED != 0 {
+        let fg = foreground.unwrap_or(background);
+        let packed = common::pack_pixel(pf, fg[0], fg[1], fg[2]);
+        common::write_pixel(buf, pf, packed);
+    }
+    if flags & HEXTILE_ANY_SUBRECTS != 0 {
+        #[allow(clippy::cast_possible_truncation)]
+        buf.put_u8(subrects.len() as u8);
+        for (x, y, w) in subrects {
+            #[allow(clippy::cast_possible_truncation)]
+            buf.put_u8(((x as u8) << 4) | (y as u8));
+            #[allow(clippy::cast_possible_truncation)]
+            buf.put_u8((((w - 1) as u8) << 4) | 0);
+        }
+    }
+
+    Some(background)
+}
+
+impl Encoding for HextileEncoding {
+    fn encode(
+        &self,
+        data: &[u8],
+        width: u16,
+        height: u16,
+        _quality: u8,
+        _compression: u8,
+    ) -> BytesMut {
+        let pf = PixelFormat::rgba32();
+        let mut buf = BytesMut::new();
+        let mut prev_bg = None;
+
+        let mut y0 = 0;
+        while y0 < height {
+            let h = TILE_SIZE.min(height - y0);
+            let mut x0 = 0;
+            while x0 < width {
+                let w = TILE_SIZE.min(width - x0);
+                prev_bg = encode_tile(&mut buf, &pf, data, width, x0, y0, w, h, prev_bg).or(prev_bg);
+                x0 += w;
+            }
+            y0 += h;
+        }
+        buf
+    }
+}
+
+impl Decoder for HextileEncoding {
+    fn decode(
+        &self,
+        data: &mut BytesMut,
+        width: u16,
+        height: u16,
+        pf: &PixelFormat,
+    ) -> io::Result<Vec<u8>> {
+        let mut out = vec![0u8; usize::from(width) * usize::from(height) * RGBA_BPP];
+        let mut bg = [0u8, 0, 0, 255];
+
+        let mut y0 = 0;
+        while y0 < height {
+            let h = TILE_SIZE.min(height - y0);
+            let mut x0 = 0;
+            while x0 < width {
+                let w = TILE_SIZE.min(width - x0);
+                bg = decode_tile(data, pf, &mut out, width, x0, y0, w, h, bg)?;
+                x0 += w;
+            }
+            y0 += h;
+        }
+        Ok(out)
+    }
+}
+
+fn decode_tile(
+    data: &mut BytesMut,
+    pf: &PixelFormat,
+    out: &mut [u8],
+    width: u16,
+    x0: u16,
+    y0: u16,
+    tile_w: u16,
+    tile_h: u16,
+    prev_bg: [u8; RGBA_BPP],
+) -> io::Result<[u8; RGBA_BPP]> {
+    if data.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "not enough bytes for Hextile tile flags",
+        ));
+    }
+    let flags = data.get_u8();
+
+    if flags & HEXTILE_RAW != 0 {
+        for y in y0..y0 + tile_h {
+            for x in x0..x0 + tile_w {
+                let pixel = common::read_pixel(data, pf)?;
+                let (r, g, b) = common::unpack_pixel(pf, pixel);
+                let offset = (usize::from(y) * usize::from(width) + usize::from(x)) * RGBA_BPP;
+                out[offset..offset + RGBA_BPP].copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+        return Ok(prev_bg);
+    }
+
+    let mut bg = prev_bg;
+    if flags & HEXTILE_BACKGROUND_SPECIFIED != 0 {
+        let pixel = common::read_pixel(data, pf)?;
+        let (r, g, b) = common::unpack_pixel(pf, pixel);
+        bg = [r, g, b, 255];
+    }
+    for y in y0..y0 + tile_h {
+        for x in x0..x0 + tile_w {
+            let offset = (usize::from(y) * usize::from(width) + usize::from(x)) * RGBA_BPP;
+            out[offset..offset + RGBA_BPP].copy_from_slice(&bg);
+        }
+    }
+
+    let mut fg = bg;
+    if flags & HEXTILE_FOREGROUND_SPECIFIED != 0 {
+        let pixel = common::read_pixel(data, pf)?;
+        let (r, g, b) = common::unpack_pixel(pf, pixel);
+        fg = [r, g, b, 255];
+    }
+
+    if flags & HEXTILE_ANY_SUBRECTS != 0 {
+        if data.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "not enough bytes for Hextile subrectangle count",
+            ));
+        }
+        let count = data.get_u8();
+        let colored = flags & HEXTILE_SUBRECTS_COLOURED != 0;
+        for _ in 0..count {
+            let pixel = if colored {
+                let pixel = common::read_pixel(data, pf)?;
+                let (r, g, b) = common::unpack_pixel(pf, pixel);
+                [r, g, b, 255]
+            } else {
+                fg
+            };
+            if data.len() < 2 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "not enough bytes for Hextile subrectangle geometry",
+                ));
+            }
+            let xy = data.get_u8();
+            let wh = data.get_u8();
+            let local_x = u16::from(xy >> 4);
+            let local_y = u16::from(xy & 0x0F);
+            let sw = u16::from(wh >> 4) + 1;
+            let sh = u16::from(wh & 0x0F) + 1;
+            if local_x + sw > tile_w || local_y + sh > tile_h {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Hextile subrectangle exceeds tile bounds",
+                ));
+            }
+            let sx = x0 + local_x;
+            let sy = y0 + local_y;
+            for row in sy..sy + sh {
+                for col in sx..sx + sw {
+                    let offset = (usize::from(row) * usize::from(width) + usize::from(col)) * RGBA_BPP;
+                    out[offset..offset + RGBA_BPP].copy_from_slice(&pixel);
+                }
+            }
+        }
+    }
+
+    Ok(bg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_encode_and_decode() {
+        let pf = PixelFormat::rgba32();
+        let (width, height) = (TILE_SIZE, TILE_SIZE);
+        let background = [10, 20, 30, 255];
+        let foreground = [200, 100, 50, 255];
+
+        let mut data = Vec::with_capacity(usize::from(width) * usize::from(height) * RGBA_BPP);
+        for y in 0..height {
+            for x in 0..width {
+                let px = if (2..5).contains(&x) && (2..5).contains(&y) {
+                    foreground
+                } else {
+                    background
+                };
+                data.extend_from_slice(&px);
+            }
+        }
+
+        let mut encoded = HextileEncoding.encode(&data, width, height, 0, 0);
+        let decoded = HextileEncoding.decode(&mut encoded, width, height, &pf).unwrap();
+
+        assert_eq!(decoded, data);
+    }
+}
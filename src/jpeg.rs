@@ -0,0 +1,196 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JPEG compression used by the photographic subencoding of Tight.
+//!
+//! When the `turbojpeg` feature is enabled, encoding goes through
+//! `libjpeg-turbo` via the `turbojpeg` crate (see `build.rs` for the linking
+//! logic). Otherwise a pure-Rust encoder is used so the crate still builds
+//! with no system dependencies.
+
+use std::io;
+
+/// Chroma subsampling mode, trading color resolution for bandwidth. Lower
+/// quality levels default to more aggressive subsampling; text-heavy
+/// content should force [`Subsampling::S444`] to avoid color fringing
+/// around glyph edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subsampling {
+    /// No subsampling: full chroma resolution. Best for text/line art.
+    S444,
+    /// Chroma halved horizontally.
+    S422,
+    /// Chroma halved both horizontally and vertically. TigerVNC's default
+    /// for low-quality photographic content.
+    S420,
+    /// Chroma discarded entirely (grayscale).
+    Gray,
+}
+
+/// JPEG encoding parameters threaded through the Tight photographic path.
+#[derive(Debug, Clone, Copy)]
+pub struct JpegConfig {
+    /// JPEG quality, 0-100.
+    pub quality: u8,
+    /// Chroma subsampling mode.
+    pub subsampling: Subsampling,
+}
+
+impl JpegConfig {
+    /// Maps a Tight quality level (0-100) to a `JpegConfig` the way
+    /// TigerVNC's Tight encoder derives JPEG quality and subsampling from
+    /// its coarse quality levels: low quality biases toward 4:2:0 for
+    /// bandwidth, high quality toward 4:4:4 for fidelity.
+    #[must_use]
+    pub fn for_quality(quality: u8) -> Self {
+        let subsampling = match quality {
+            80..=100 => Subsampling::S444,
+            60..=79 => Subsampling::S422,
+            _ => Subsampling::S420,
+        };
+        Self {
+            quality,
+            subsampling,
+        }
+    }
+}
+
+impl Default for JpegConfig {
+    fn default() -> Self {
+        Self::for_quality(75)
+    }
+}
+
+/// Encodes an RGB buffer (3 bytes per pixel) as a JPEG baseline stream.
+///
+/// # Errors
+///
+/// Returns `Err(io::Error)` if the underlying encoder fails.
+pub fn encode_jpeg(data: &[u8], width: u16, height: u16, config: JpegConfig) -> io::Result<Vec<u8>> {
+    #[cfg(feature = "turbojpeg")]
+    {
+        encode_jpeg_turbo(data, width, height, config)
+    }
+    #[cfg(not(feature = "turbojpeg"))]
+    {
+        encode_jpeg_fallback(data, width, height, config)
+    }
+}
+
+#[cfg(feature = "turbojpeg")]
+fn encode_jpeg_turbo(
+    data: &[u8],
+    width: u16,
+    height: u16,
+    config: JpegConfig,
+) -> io::Result<Vec<u8>> {
+    use turbojpeg::{compress, Image, PixelFormat as TjPixelFormat, Subsamp};
+
+    let image = Image {
+        pixels: data,
+        width: usize::from(width),
+        pitch: usize::from(width) * 3,
+        height: usize::from(height),
+        format: TjPixelFormat::RGB,
+    };
+
+    let subsamp = match config.subsampling {
+        Subsampling::S444 => Subsamp::None,
+        Subsampling::S422 => Subsamp::Sub2x1,
+        Subsampling::S420 => Subsamp::Sub2x2,
+        Subsampling::Gray => Subsamp::Gray,
+    };
+
+    compress(image, i32::from(config.quality), subsamp)
+        .map(|buf| buf.to_vec())
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+}
+
+#[cfg(not(feature = "turbojpeg"))]
+fn encode_jpeg_fallback(
+    data: &[u8],
+    width: u16,
+    height: u16,
+    config: JpegConfig,
+) -> io::Result<Vec<u8>> {
+    use jpeg_encoder::{ColorType, Encoder, SamplingFactor};
+
+    let mut out = Vec::new();
+    let mut encoder = Encoder::new(&mut out, config.quality);
+
+    // jpeg_encoder has no "gray chroma subsampling" ratio; true grayscale
+    // means feeding it luma-only samples under ColorType::Luma instead.
+    if config.subsampling == Subsampling::Gray {
+        let luma: Vec<u8> = data
+            .chunks_exact(3)
+            .map(|px| {
+                let (r, g, b) = (u16::from(px[0]), u16::from(px[1]), u16::from(px[2]));
+                #[allow(clippy::cast_possible_truncation)]
+                // the weighted sum of three u8 channels divided by 256 always fits in u8
+                let luma = ((r * 77 + g * 150 + b * 29) / 256) as u8;
+                luma
+            })
+            .collect();
+        encoder
+            .encode(&luma, width, height, ColorType::Luma)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        return Ok(out);
+    }
+
+    let sampling = match config.subsampling {
+        Subsampling::S444 => SamplingFactor::R_4_4_4,
+        Subsampling::S422 => SamplingFactor::R_4_2_2,
+        Subsampling::S420 => SamplingFactor::R_4_2_0,
+        Subsampling::Gray => unreachable!("handled above"),
+    };
+    encoder.set_sampling_factor(sampling);
+    encoder
+        .encode(data, width, height, ColorType::Rgb)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    Ok(out)
+}
+
+/// Decodes a JPEG stream into an RGB buffer (3 bytes per pixel).
+///
+/// # Errors
+///
+/// Returns `Err(io::Error)` if the stream is not a valid JPEG image.
+pub fn decode_jpeg(data: &[u8]) -> io::Result<Vec<u8>> {
+    #[cfg(feature = "turbojpeg")]
+    {
+        use turbojpeg::{decompress, PixelFormat as TjPixelFormat};
+
+        let image = decompress(data, TjPixelFormat::RGB)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        Ok(image.pixels)
+    }
+    #[cfg(not(feature = "turbojpeg"))]
+    {
+        let mut decoder = jpeg_decoder::Decoder::new(data);
+        let pixels = decoder
+            .decode()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+
+        // A JPEG encoded with Subsampling::Gray (see encode_jpeg_fallback)
+        // decodes back as single-channel luma, not 3-channel RGB; every
+        // caller of decode_jpeg expects RGB24, so replicate luma into R/G/B.
+        match decoder.info().map(|info| info.pixel_format) {
+            Some(jpeg_decoder::PixelFormat::L8) => Ok(pixels
+                .iter()
+                .flat_map(|&l| [l, l, l])
+                .collect()),
+            _ => Ok(pixels),
+        }
+    }
+}
@@ -26,11 +26,14 @@ use std::io;
 
 // Encoding modules
 pub mod common;
+pub mod copyrect;
 pub mod corre;
 pub mod hextile;
 pub mod jpeg;
+pub mod pseudo;
 pub mod raw;
 pub mod rre;
+pub mod session;
 pub mod tight;
 pub mod tightpng;
 pub mod translate;
@@ -74,18 +77,34 @@ pub const ENCODING_ZYWRLE: i32 = 17;
 /// Encoding type: `TightPng`.
 pub const ENCODING_TIGHTPNG: i32 = -260;
 
+// Pseudo-encoding types: not framebuffer data, but still negotiated and
+// framed as rectangles.
+
+/// Pseudo-encoding: `RichCursor`, a cursor image in the negotiated pixel
+/// format plus a 1-bit-per-pixel transparency mask.
+pub const PSEUDO_ENCODING_RICH_CURSOR: i32 = -239;
+
+/// Pseudo-encoding: `XCursor`, a 2-color cursor with separate bitmap and
+/// mask planes.
+pub const PSEUDO_ENCODING_X_CURSOR: i32 = -240;
+
+/// Pseudo-encoding: `DesktopSize`, a server-initiated resolution change.
+pub const PSEUDO_ENCODING_DESKTOP_SIZE: i32 = -223;
+
 // Re-export common types
 pub use common::*;
+pub use copyrect::{CopyRectEncoder, CopyRectMatch, DirtyRect};
 pub use corre::CorRreEncoding;
 pub use hextile::HextileEncoding;
 pub use raw::RawEncoding;
 pub use rre::RreEncoding;
+pub use session::EncodingSession;
 pub use tight::TightEncoding;
-pub use tightpng::TightPngEncoding;
+pub use tightpng::{FilterStrategy, TightPngEncoding};
 pub use zlib::encode_zlib_persistent;
 pub use zlibhex::encode_zlibhex_persistent;
 pub use zrle::encode_zrle_persistent;
-pub use zywrle::zywrle_analyze;
+pub use zywrle::{encode_zywrle_persistent, zywrle_analyze};
 
 // Hextile subencoding flags
 
@@ -412,8 +431,64 @@ pub fn get_encoder(encoding_type: i32) -> Option<Box<dyn Encoding>> {
         ENCODING_RRE => Some(Box::new(RreEncoding)),
         ENCODING_CORRE => Some(Box::new(CorRreEncoding)),
         ENCODING_HEXTILE => Some(Box::new(HextileEncoding)),
-        ENCODING_TIGHT => Some(Box::new(TightEncoding)),
-        ENCODING_TIGHTPNG => Some(Box::new(TightPngEncoding)),
+        ENCODING_TIGHT => Some(Box::new(TightEncoding::default())),
+        ENCODING_TIGHTPNG => Some(Box::new(TightPngEncoding::default())),
+        _ => None,
+    }
+}
+
+/// Trait defining the interface for RFB decoding implementations: the
+/// inverse of [`Encoding`], turning an encoded rectangle back into RGBA.
+pub trait Decoder {
+    /// Decodes an RFB-compatible byte stream into raw pixel data.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The encoded rectangle body; consumed bytes are advanced
+    ///   out of the buffer
+    /// * `width` - Width of the rectangle
+    /// * `height` - Height of the rectangle
+    /// * `pf` - The `PixelFormat` the encoded bytes are shaped by
+    ///
+    /// # Returns
+    ///
+    /// RGBA pixel data (4 bytes per pixel) on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(io::Error)` if `data` does not hold a complete,
+    /// well-formed rectangle for this encoding.
+    fn decode(
+        &self,
+        data: &mut BytesMut,
+        width: u16,
+        height: u16,
+        pf: &PixelFormat,
+    ) -> io::Result<Vec<u8>>;
+}
+
+/// Creates a decoder instance for the specified encoding type.
+///
+/// # Arguments
+///
+/// * `encoding_type` - The RFB encoding type constant
+///
+/// # Returns
+///
+/// `Some(Box<dyn Decoder>)` if the encoding is supported, `None` otherwise
+#[must_use]
+pub fn get_decoder(encoding_type: i32) -> Option<Box<dyn Decoder>> {
+    match encoding_type {
+        ENCODING_RAW => Some(Box::new(RawEncoding)),
+        ENCODING_RRE => Some(Box::new(RreEncoding)),
+        ENCODING_CORRE => Some(Box::new(CorRreEncoding)),
+        ENCODING_HEXTILE => Some(Box::new(HextileEncoding)),
+        ENCODING_TIGHT => Some(Box::new(TightEncoding::default())),
+        ENCODING_TIGHTPNG => Some(Box::new(TightPngEncoding::default())),
+        ENCODING_ZLIB => Some(Box::new(zlib::ZlibEncoding::new())),
+        ENCODING_ZLIBHEX => Some(Box::new(zlibhex::ZlibHexEncoding::new())),
+        ENCODING_ZRLE => Some(Box::new(zrle::ZrleEncoding::new())),
+        ENCODING_ZYWRLE => Some(Box::new(zywrle::ZywrleEncoding::new(1))),
         _ => None,
     }
 }
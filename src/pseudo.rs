@@ -0,0 +1,130 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pseudo-encodings: rectangles that don't carry framebuffer pixels at all,
+//! but repurpose the `FramebufferUpdate` rectangle framing to deliver
+//! client-side cursor images and desktop resize notifications.
+
+use crate::common::write_rectangle_header;
+use crate::{PixelFormat, PSEUDO_ENCODING_DESKTOP_SIZE, PSEUDO_ENCODING_RICH_CURSOR, PSEUDO_ENCODING_X_CURSOR};
+use bytes::{BufMut, BytesMut};
+
+/// Number of bytes per scanline needed to hold `width` 1-bit-per-pixel
+/// values, padded up to a whole byte.
+fn bitmask_stride(width: u16) -> usize {
+    usize::from(width).div_ceil(8)
+}
+
+/// Serializes a `RichCursor` pseudo-encoding rectangle: the rectangle
+/// header carries the hotspot as (x, y) and the cursor size as
+/// (width, height); the body is the cursor pixels in `pf`, followed by a
+/// 1-bpp transparency mask padded to a whole byte per scanline.
+///
+/// `mask` must contain one `bool` per pixel, row-major, `true` meaning
+/// opaque.
+#[must_use]
+pub fn write_rich_cursor(
+    hotspot_x: u16,
+    hotspot_y: u16,
+    width: u16,
+    height: u16,
+    pf: &PixelFormat,
+    pixels: &[(u8, u8, u8)],
+    mask: &[bool],
+) -> BytesMut {
+    let mut buf = BytesMut::new();
+    write_rectangle_header(
+        &mut buf,
+        hotspot_x,
+        hotspot_y,
+        width,
+        height,
+        PSEUDO_ENCODING_RICH_CURSOR,
+    );
+
+    for &(r, g, b) in pixels {
+        let pixel = crate::common::pack_pixel(pf, r, g, b);
+        crate::common::write_pixel(&mut buf, pf, pixel);
+    }
+
+    let stride = bitmask_stride(width);
+    for row in mask.chunks(usize::from(width)) {
+        let mut scanline = vec![0u8; stride];
+        for (i, &opaque) in row.iter().enumerate() {
+            if opaque {
+                scanline[i / 8] |= 0x80 >> (i % 8);
+            }
+        }
+        buf.put_slice(&scanline);
+    }
+
+    buf
+}
+
+/// Serializes an `XCursor` pseudo-encoding rectangle: hotspot and size in
+/// the header as with `RichCursor`, then a 2-color RGB palette
+/// (foreground, background), a 1-bpp bitmap, and a 1-bpp mask, each padded
+/// to a whole byte per scanline.
+#[must_use]
+pub fn write_x_cursor(
+    hotspot_x: u16,
+    hotspot_y: u16,
+    width: u16,
+    height: u16,
+    foreground: (u8, u8, u8),
+    background: (u8, u8, u8),
+    bitmap: &[bool],
+    mask: &[bool],
+) -> BytesMut {
+    let mut buf = BytesMut::new();
+    write_rectangle_header(
+        &mut buf,
+        hotspot_x,
+        hotspot_y,
+        width,
+        height,
+        PSEUDO_ENCODING_X_CURSOR,
+    );
+
+    buf.put_u8(foreground.0);
+    buf.put_u8(foreground.1);
+    buf.put_u8(foreground.2);
+    buf.put_u8(background.0);
+    buf.put_u8(background.1);
+    buf.put_u8(background.2);
+
+    let stride = bitmask_stride(width);
+    for plane in [bitmap, mask] {
+        for row in plane.chunks(usize::from(width)) {
+            let mut scanline = vec![0u8; stride];
+            for (i, &set) in row.iter().enumerate() {
+                if set {
+                    scanline[i / 8] |= 0x80 >> (i % 8);
+                }
+            }
+            buf.put_slice(&scanline);
+        }
+    }
+
+    buf
+}
+
+/// Serializes a `DesktopSize` pseudo-encoding rectangle: the new width and
+/// height go in the rectangle header; the body is empty.
+#[must_use]
+pub fn write_desktop_size(width: u16, height: u16) -> BytesMut {
+    let mut buf = BytesMut::new();
+    write_rectangle_header(&mut buf, 0, 0, width, height, PSEUDO_ENCODING_DESKTOP_SIZE);
+    buf
+}
@@ -0,0 +1,89 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Raw encoding: pixel data is sent (and read back) uncompressed.
+
+use crate::common::{self, RGBA_BPP};
+use crate::{Decoder, Encoding, PixelFormat};
+use bytes::{BufMut, BytesMut};
+use std::io;
+
+/// Raw encoding. The simplest and most bandwidth-hungry RFB encoding: pixels
+/// are sent exactly as received, with no compression.
+pub struct RawEncoding;
+
+impl Encoding for RawEncoding {
+    fn encode(
+        &self,
+        data: &[u8],
+        _width: u16,
+        _height: u16,
+        _quality: u8,
+        _compression: u8,
+    ) -> BytesMut {
+        let mut buf = BytesMut::with_capacity(data.len());
+        buf.put_slice(data);
+        buf
+    }
+}
+
+impl Decoder for RawEncoding {
+    fn decode(
+        &self,
+        data: &mut BytesMut,
+        width: u16,
+        height: u16,
+        pf: &PixelFormat,
+    ) -> io::Result<Vec<u8>> {
+        let bpp = usize::from(pf.bits_per_pixel / 8);
+        let pixel_count = usize::from(width) * usize::from(height);
+        let needed = pixel_count * bpp;
+        if data.len() < needed {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "not enough bytes for raw rectangle",
+            ));
+        }
+
+        let mut out = Vec::with_capacity(pixel_count * RGBA_BPP);
+        for _ in 0..pixel_count {
+            let pixel = common::read_pixel(data, pf)?;
+            let (r, g, b) = common::unpack_pixel(pf, pixel);
+            out.extend_from_slice(&[r, g, b, 255]);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_encode_and_decode() {
+        let pf = PixelFormat::rgba32();
+        let (width, height) = (2, 2);
+        let data: Vec<u8> = vec![
+            10, 20, 30, 255, //
+            40, 50, 60, 255, //
+            70, 80, 90, 255, //
+            100, 110, 120, 255,
+        ];
+
+        let mut encoded = RawEncoding.encode(&data, width, height, 0, 0);
+        let decoded = RawEncoding.decode(&mut encoded, width, height, &pf).unwrap();
+
+        assert_eq!(decoded, data);
+    }
+}
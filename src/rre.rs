@@ -0,0 +1,176 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rise-and-Run-length Encoding (RRE): a background pixel plus a list of
+//! solid-colored subrectangles that differ from it.
+
+use crate::common::{self, pixels_equal, RGBA_BPP};
+use crate::{Decoder, Encoding, PixelFormat};
+use bytes::{Buf, BufMut, BytesMut};
+use std::collections::HashMap;
+use std::io;
+
+/// RRE encoding: a background pixel plus axis-aligned solid subrectangles.
+pub struct RreEncoding;
+
+/// Finds the most common pixel in `data`, used as the RRE background color.
+fn most_common_pixel(data: &[u8]) -> [u8; RGBA_BPP] {
+    let mut counts: HashMap<[u8; RGBA_BPP], usize> = HashMap::new();
+    for px in data.chunks_exact(RGBA_BPP) {
+        let key = [px[0], px[1], px[2], px[3]];
+        *counts.entry(key).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map_or([0, 0, 0, 0], |(pixel, _)| pixel)
+}
+
+/// Scans `data` row by row and collects maximal horizontal runs of pixels
+/// that differ from `background`, as `(x, y, width, pixel)` subrectangles.
+fn background_runs(data: &[u8], width: u16, height: u16, background: &[u8]) -> Vec<(u16, u16, u16, [u8; RGBA_BPP])> {
+    let mut runs = Vec::new();
+    for y in 0..height {
+        let mut x = 0u16;
+        while x < width {
+            let px = common::pixel_at(data, width, x, y);
+            if pixels_equal(px, background) {
+                x += 1;
+                continue;
+            }
+            let run_pixel = [px[0], px[1], px[2], px[3]];
+            let start = x;
+            while x < width && pixels_equal(common::pixel_at(data, width, x, y), &run_pixel) {
+                x += 1;
+            }
+            runs.push((start, y, x - start, run_pixel));
+        }
+    }
+    runs
+}
+
+impl Encoding for RreEncoding {
+    fn encode(
+        &self,
+        data: &[u8],
+        width: u16,
+        height: u16,
+        _quality: u8,
+        _compression: u8,
+    ) -> BytesMut {
+        let pf = PixelFormat::rgba32();
+        let background = most_common_pixel(data);
+        let runs = background_runs(data, width, height, &background);
+
+        let mut buf = BytesMut::with_capacity(8 + runs.len() * 12);
+        buf.put_u32(u32::try_from(runs.len()).unwrap_or(u32::MAX));
+        let bg_pixel = common::pack_pixel(&pf, background[0], background[1], background[2]);
+        common::write_pixel(&mut buf, &pf, bg_pixel);
+
+        for (x, y, w, pixel) in runs {
+            let packed = common::pack_pixel(&pf, pixel[0], pixel[1], pixel[2]);
+            common::write_pixel(&mut buf, &pf, packed);
+            buf.put_u16(x);
+            buf.put_u16(y);
+            buf.put_u16(w);
+            buf.put_u16(1);
+        }
+        buf
+    }
+}
+
+impl Decoder for RreEncoding {
+    fn decode(
+        &self,
+        data: &mut BytesMut,
+        width: u16,
+        height: u16,
+        pf: &PixelFormat,
+    ) -> io::Result<Vec<u8>> {
+        if data.len() < 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "not enough bytes for RRE subrectangle count",
+            ));
+        }
+        let count = data.get_u32();
+        let background = common::read_pixel(data, pf)?;
+        let (br, bg, bb) = common::unpack_pixel(pf, background);
+
+        let mut out = vec![0u8; usize::from(width) * usize::from(height) * RGBA_BPP];
+        for chunk in out.chunks_exact_mut(RGBA_BPP) {
+            chunk.copy_from_slice(&[br, bg, bb, 255]);
+        }
+
+        for _ in 0..count {
+            let pixel = common::read_pixel(data, pf)?;
+            let (r, g, b) = common::unpack_pixel(pf, pixel);
+            if data.len() < 8 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "not enough bytes for RRE subrectangle header",
+                ));
+            }
+            let x = data.get_u16();
+            let y = data.get_u16();
+            let w = data.get_u16();
+            let h = data.get_u16();
+            if u32::from(x) + u32::from(w) > u32::from(width)
+                || u32::from(y) + u32::from(h) > u32::from(height)
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "RRE subrectangle exceeds framebuffer bounds",
+                ));
+            }
+            for row in y..y + h {
+                for col in x..x + w {
+                    let offset = (usize::from(row) * usize::from(width) + usize::from(col)) * RGBA_BPP;
+                    out[offset..offset + RGBA_BPP].copy_from_slice(&[r, g, b, 255]);
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_encode_and_decode() {
+        let pf = PixelFormat::rgba32();
+        let (width, height) = (4, 4);
+        let background = [10, 20, 30, 255];
+        let foreground = [200, 100, 50, 255];
+
+        let mut data = Vec::with_capacity(usize::from(width) * usize::from(height) * RGBA_BPP);
+        for y in 0..height {
+            for x in 0..width {
+                let px = if (1..3).contains(&x) && y == 2 {
+                    foreground
+                } else {
+                    background
+                };
+                data.extend_from_slice(&px);
+            }
+        }
+
+        let mut encoded = RreEncoding.encode(&data, width, height, 0, 0);
+        let decoded = RreEncoding.decode(&mut encoded, width, height, &pf).unwrap();
+
+        assert_eq!(decoded, data);
+    }
+}
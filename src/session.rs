@@ -0,0 +1,175 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Connection-scoped state for the `deflate`-backed encodings (Zlib,
+//! `ZlibHex`, ZRLE, ZYWRLE).
+//!
+//! RFB requires the `deflate` history for these encodings to persist across
+//! every rectangle of a connection, not just within one message. The
+//! `Encoding` trait has no room to carry that state (`encode` takes `&self`
+//! and is handed a fresh rectangle each time), so it lives here instead.
+
+use crate::{
+    zlib, zlibhex, zrle, zywrle, ENCODING_ZLIB, ENCODING_ZLIBHEX, ENCODING_ZRLE, ENCODING_ZYWRLE,
+};
+use bytes::BytesMut;
+use flate2::{Compress, Compression, FlushCompress, Status};
+use std::io;
+
+/// Runs `stream.compress_vec(input, ..., flush)` to completion.
+///
+/// `compress_vec` only ever writes into `output`'s *existing* spare capacity
+/// and never reallocates it, and it may stop having consumed only part of
+/// `input` if that capacity runs out before the flush is fully emitted. A
+/// single call sized to `input.len()` is not enough headroom in general: a
+/// `Sync` flush appends a few bytes of its own, and incompressible input can
+/// expand rather than shrink. This loops, tracking how much input has been
+/// consumed via `total_in()`, growing `output` and retrying until `stream`
+/// reports the flush is done and no input remains.
+///
+/// This is also why `Compress::set_level` never runs on these streams:
+/// re-tuning a persistent `deflate` stream mid-connection is only possible
+/// behind flate2's non-default `any_zlib` feature, so the compression level
+/// is fixed for the life of the stream (set when it's created below) rather
+/// than varying per rectangle.
+pub(crate) fn compress_to_vec(stream: &mut Compress, input: &[u8], flush: FlushCompress) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len() + 16);
+    let in_start = stream.total_in();
+    loop {
+        output.reserve(16);
+        let consumed = usize::try_from(stream.total_in() - in_start).unwrap_or(input.len());
+        let remaining = &input[consumed.min(input.len())..];
+        let status = stream
+            .compress_vec(remaining, &mut output, flush)
+            .unwrap_or(Status::StreamEnd);
+
+        let all_input_consumed =
+            usize::try_from(stream.total_in() - in_start).unwrap_or(0) >= input.len();
+        // zlib only asks for more output space when it filled everything it
+        // was offered; spare capacity left over after a call means it had
+        // room to finish flushing. Stop once that's true with all input
+        // consumed (or the stream reports it's fully done outright).
+        let fully_flushed = output.len() < output.capacity();
+        if status == Status::StreamEnd || (all_input_consumed && fully_flushed) {
+            break;
+        }
+    }
+    output
+}
+
+/// Default wavelet detail level ZYWRLE uses within a session.
+const DEFAULT_ZYWRLE_LEVEL: u8 = 1;
+
+/// Holds the per-encoding `deflate` streams for one RFB connection.
+///
+/// A connection may use more than one of the `deflate`-backed encodings
+/// across its lifetime (e.g. a client that renegotiates encodings), so each
+/// stream is created lazily the first time its encoding type is used.
+pub struct EncodingSession {
+    zlib: Option<Compress>,
+    zlibhex: Option<Compress>,
+    zrle: Option<Compress>,
+    zywrle: Option<Compress>,
+    zywrle_level: u8,
+}
+
+impl EncodingSession {
+    /// Creates a new session, eagerly initializing the stream for
+    /// `encoding_type`.
+    ///
+    /// # Returns
+    ///
+    /// `Some(EncodingSession)` if `encoding_type` is one of Zlib (6),
+    /// `ZlibHex` (8), ZRLE (16), or ZYWRLE (17); `None` otherwise.
+    #[must_use]
+    pub fn new_session(encoding_type: i32) -> Option<Self> {
+        if !matches!(
+            encoding_type,
+            ENCODING_ZLIB | ENCODING_ZLIBHEX | ENCODING_ZRLE | ENCODING_ZYWRLE
+        ) {
+            return None;
+        }
+
+        let mut session = Self {
+            zlib: None,
+            zlibhex: None,
+            zrle: None,
+            zywrle: None,
+            zywrle_level: DEFAULT_ZYWRLE_LEVEL,
+        };
+        session.stream_for(encoding_type);
+        Some(session)
+    }
+
+    /// Overrides the wavelet detail level ZYWRLE uses in this session.
+    pub fn set_zywrle_level(&mut self, level: u8) {
+        self.zywrle_level = level;
+    }
+
+    fn stream_for(&mut self, encoding_type: i32) -> &mut Compress {
+        let slot = match encoding_type {
+            ENCODING_ZLIB => &mut self.zlib,
+            ENCODING_ZLIBHEX => &mut self.zlibhex,
+            ENCODING_ZRLE => &mut self.zrle,
+            ENCODING_ZYWRLE => &mut self.zywrle,
+            _ => unreachable!("validated by new_session/encode"),
+        };
+        slot.get_or_insert_with(|| Compress::new(Compression::default(), true))
+    }
+
+    /// Encodes one rectangle using the persistent stream for
+    /// `encoding_type`, creating that stream on first use.
+    ///
+    /// `compression` is accepted for parity with [`crate::Encoding::encode`]
+    /// but has no effect here: these streams compress at a fixed level for
+    /// the life of the connection (see [`compress_to_vec`] for why).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(io::Error)` if `encoding_type` is not one of Zlib,
+    /// `ZlibHex`, ZRLE, or ZYWRLE.
+    pub fn encode(
+        &mut self,
+        encoding_type: i32,
+        data: &[u8],
+        width: u16,
+        height: u16,
+        quality: u8,
+        compression: u8,
+    ) -> io::Result<BytesMut> {
+        if !matches!(
+            encoding_type,
+            ENCODING_ZLIB | ENCODING_ZLIBHEX | ENCODING_ZRLE | ENCODING_ZYWRLE
+        ) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "encoding type is not a persistent-stream encoding",
+            ));
+        }
+
+        let level = self.zywrle_level;
+        let stream = self.stream_for(encoding_type);
+        Ok(match encoding_type {
+            ENCODING_ZLIB => zlib::encode_zlib_persistent(stream, data, width, height, compression),
+            ENCODING_ZLIBHEX => {
+                zlibhex::encode_zlibhex_persistent(stream, data, width, height, compression)
+            }
+            ENCODING_ZRLE => zrle::encode_zrle_persistent(stream, data, width, height, compression),
+            ENCODING_ZYWRLE => {
+                zywrle::encode_zywrle_persistent(stream, data, width, height, quality, level, compression)
+            }
+            _ => unreachable!("validated above"),
+        })
+    }
+}
@@ -0,0 +1,214 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tight encoding: a solid-fill fast path, a JPEG path for photographic
+//! content, and a zlib-compressed raw-pixel fallback.
+//!
+//! Each call to [`Encoding::encode`] compresses with a fresh `zlib` stream,
+//! since the `Encoding` trait has no way to carry state across rectangles.
+//! [`crate::EncodingSession`] keeps connection-scoped `deflate` streams only
+//! for Zlib, `ZlibHex`, ZRLE and ZYWRLE; it does not cover Tight.
+
+use crate::common::{self, write_compact_length, RGBA_BPP};
+use crate::jpeg::{self, JpegConfig};
+use crate::{Decoder, Encoding, PixelFormat};
+use bytes::{BufMut, BytesMut};
+use flate2::{write::ZlibEncoder, Compression};
+use std::io::{self, Read, Write};
+
+/// Compression-control bit marking a solid-fill subrectangle.
+const TIGHT_FILL: u8 = 0x80;
+
+/// Compression-control bit marking a JPEG subrectangle.
+const TIGHT_JPEG: u8 = 0x90;
+
+/// Minimum pixel count below which Tight always falls back to a solid fill
+/// or zlib, since JPEG overhead dominates for tiny rectangles.
+const JPEG_MIN_PIXELS: usize = 1024;
+
+/// Tight encoding: fill / JPEG / zlib-compressed-raw, chosen per rectangle.
+///
+/// When `jpeg_config` is set, it overrides the quality-derived
+/// [`JpegConfig`] (see [`JpegConfig::for_quality`]) that is otherwise
+/// derived from the `quality` argument passed to `encode`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TightEncoding {
+    jpeg_config: Option<JpegConfig>,
+}
+
+impl TightEncoding {
+    /// Creates a Tight encoder that always uses `jpeg_config` for its JPEG
+    /// subencoding, instead of deriving one from the `quality` argument.
+    #[must_use]
+    pub fn with_jpeg_config(jpeg_config: JpegConfig) -> Self {
+        Self {
+            jpeg_config: Some(jpeg_config),
+        }
+    }
+}
+
+fn rgba_to_rgb(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / RGBA_BPP * 3);
+    for px in data.chunks_exact(RGBA_BPP) {
+        out.extend_from_slice(&px[..3]);
+    }
+    out
+}
+
+fn is_solid(data: &[u8]) -> Option<[u8; 3]> {
+    let first = &data[..3];
+    if data.chunks_exact(RGBA_BPP).all(|px| px[..3] == *first) {
+        Some([first[0], first[1], first[2]])
+    } else {
+        None
+    }
+}
+
+fn zlib_compress(data: &[u8], compression: u8) -> Vec<u8> {
+    let level = Compression::new(u32::from(compression.min(9)));
+    let mut encoder = ZlibEncoder::new(Vec::new(), level);
+    let _ = encoder.write_all(data);
+    encoder.finish().unwrap_or_default()
+}
+
+fn zlib_decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut decoder = flate2::read::ZlibDecoder::new(data);
+    let mut out = Vec::new();
+    decoder.read_to_end(&mut out)?;
+    Ok(out)
+}
+
+impl Encoding for TightEncoding {
+    fn encode(
+        &self,
+        data: &[u8],
+        width: u16,
+        height: u16,
+        quality: u8,
+        compression: u8,
+    ) -> BytesMut {
+        let pixel_count = usize::from(width) * usize::from(height);
+        let mut buf = BytesMut::new();
+
+        if let Some(rgb) = is_solid(data) {
+            buf.put_u8(TIGHT_FILL);
+            buf.put_slice(&rgb);
+            return buf;
+        }
+
+        let rgb = rgba_to_rgb(data);
+
+        if quality < 100 && pixel_count >= JPEG_MIN_PIXELS {
+            let config = self
+                .jpeg_config
+                .unwrap_or_else(|| JpegConfig::for_quality(quality.max(1)));
+            if let Ok(jpeg_data) = jpeg::encode_jpeg(&rgb, width, height, config) {
+                buf.put_u8(TIGHT_JPEG);
+                write_compact_length(&mut buf, jpeg_data.len());
+                buf.put_slice(&jpeg_data);
+                return buf;
+            }
+        }
+
+        let compressed = zlib_compress(&rgb, compression);
+        buf.put_u8(0);
+        write_compact_length(&mut buf, compressed.len());
+        buf.put_slice(&compressed);
+        buf
+    }
+}
+
+impl Decoder for TightEncoding {
+    fn decode(
+        &self,
+        data: &mut BytesMut,
+        width: u16,
+        height: u16,
+        pf: &PixelFormat,
+    ) -> io::Result<Vec<u8>> {
+        if data.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "not enough bytes for Tight control byte",
+            ));
+        }
+        let control = bytes::Buf::get_u8(data);
+        let pixel_count = usize::from(width) * usize::from(height);
+
+        let rgb: Vec<u8> = if control == TIGHT_FILL {
+            if data.len() < 3 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "not enough bytes for Tight fill color",
+                ));
+            }
+            let color = [
+                bytes::Buf::get_u8(data),
+                bytes::Buf::get_u8(data),
+                bytes::Buf::get_u8(data),
+            ];
+            color.repeat(pixel_count)
+        } else if control == TIGHT_JPEG {
+            let len = common::read_compact_length(data)?;
+            if data.len() < len {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "not enough bytes for Tight JPEG payload",
+                ));
+            }
+            let payload = data.split_to(len);
+            jpeg::decode_jpeg(&payload)?
+        } else {
+            let len = common::read_compact_length(data)?;
+            if data.len() < len {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "not enough bytes for Tight zlib payload",
+                ));
+            }
+            let payload = data.split_to(len);
+            zlib_decompress(&payload)?
+        };
+
+        let mut out = Vec::with_capacity(pixel_count * RGBA_BPP);
+        for px in rgb.chunks_exact(3) {
+            let packed = common::pack_pixel(pf, px[0], px[1], px[2]);
+            let (r, g, b) = common::unpack_pixel(pf, packed);
+            out.extend_from_slice(&[r, g, b, 255]);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_a_solid_fill_through_encode_and_decode() {
+        // Solid-color data always takes the lossless TIGHT_FILL path, so
+        // this exercises the round trip without JPEG's lossy quantization.
+        let pf = PixelFormat::rgba32();
+        let (width, height) = (4, 4);
+        let pixel = [60, 120, 180, 255];
+        let data: Vec<u8> = pixel.repeat(usize::from(width) * usize::from(height));
+
+        let mut encoded = TightEncoding::default().encode(&data, width, height, 100, 6);
+        let decoded = TightEncoding::default()
+            .decode(&mut encoded, width, height, &pf)
+            .unwrap();
+
+        assert_eq!(decoded, data);
+    }
+}
@@ -0,0 +1,346 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `TightPng`: the lossless variant of Tight used by the `TightPng`
+//! pseudo-encoding. Subrectangles are always PNG (never JPEG), so clients
+//! that cannot decode JPEG can still negotiate a compact encoding.
+
+use crate::common::RGBA_BPP;
+use crate::{Decoder, Encoding, PixelFormat, TIGHT_PNG};
+use bytes::{BufMut, BytesMut};
+use flate2::{write::ZlibEncoder, Compression};
+use std::io::{self, Read, Write};
+
+/// Number of bytes per pixel in the PNG scanlines this encoder produces
+/// (8-bit RGB, no alpha).
+const PNG_BPP: usize = 3;
+
+/// How `TightPngEncoding` chooses a PNG scanline filter before deflating,
+/// mirroring the `FilterStrategy` knob lodepng exposes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterStrategy {
+    /// Always use filter type 0 (None).
+    #[default]
+    Zero,
+    /// Always use filter type 2 (Up).
+    Up,
+    /// Always use filter type 4 (Paeth).
+    Paeth,
+    /// Try all five filter types per scanline and keep the one with the
+    /// smallest sum of absolute filtered-byte magnitudes.
+    Adaptive,
+}
+
+/// `TightPng` encoding: always-PNG Tight, for JPEG-incapable clients.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TightPngEncoding {
+    filter_strategy: FilterStrategy,
+}
+
+impl TightPngEncoding {
+    /// Creates a `TightPng` encoder using the given scanline filter strategy.
+    #[must_use]
+    pub fn with_filter_strategy(filter_strategy: FilterStrategy) -> Self {
+        Self { filter_strategy }
+    }
+}
+
+fn rgba_to_rgb(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / RGBA_BPP * 3);
+    for px in data.chunks_exact(RGBA_BPP) {
+        out.extend_from_slice(&px[..3]);
+    }
+    out
+}
+
+/// Applies PNG filter type `filter_type` (0=None, 1=Sub, 2=Up, 3=Average,
+/// 4=Paeth) to scanline `row`, given the previous scanline's *filtered*
+/// bytes in `above` (empty for the first row). Out-of-bounds left/upper-left
+/// neighbors are treated as zero.
+fn apply_filter(row: &[u8], above: &[u8], filter_type: u8) -> Vec<u8> {
+    let mut out = vec![0u8; row.len()];
+    for i in 0..row.len() {
+        let raw = row[i];
+        let left = if i >= PNG_BPP { row[i - PNG_BPP] } else { 0 };
+        let up = above.get(i).copied().unwrap_or(0);
+        let up_left = if i >= PNG_BPP {
+            above.get(i - PNG_BPP).copied().unwrap_or(0)
+        } else {
+            0
+        };
+
+        out[i] = match filter_type {
+            1 => raw.wrapping_sub(left),
+            2 => raw.wrapping_sub(up),
+            3 => {
+                let avg = (u16::from(left) + u16::from(up)) / 2;
+                #[allow(clippy::cast_possible_truncation)]
+                raw.wrapping_sub(avg as u8)
+            }
+            4 => raw.wrapping_sub(paeth_predictor(left, up, up_left)),
+            _ => raw,
+        };
+    }
+    out
+}
+
+/// The PNG Paeth predictor: picks whichever of `left`, `up`, `upper_left`
+/// is closest to `left + up - upper_left`.
+fn paeth_predictor(left: u8, up: u8, upper_left: u8) -> u8 {
+    let p = i32::from(left) + i32::from(up) - i32::from(upper_left);
+    let pa = (p - i32::from(left)).abs();
+    let pb = (p - i32::from(up)).abs();
+    let pc = (p - i32::from(upper_left)).abs();
+    if pa <= pb && pa <= pc {
+        left
+    } else if pb <= pc {
+        up
+    } else {
+        upper_left
+    }
+}
+
+/// Sum of the filtered bytes interpreted as signed magnitudes (`|b as i8|`),
+/// the heuristic `FilterStrategy::Adaptive` minimizes per scanline.
+fn filtered_sum(row: &[u8]) -> u32 {
+    row.iter().map(|&b| u32::from((b as i8).unsigned_abs())).sum()
+}
+
+/// Chooses a filter type for `row` according to `strategy` and returns
+/// `(filter_type, filtered_bytes)`.
+fn choose_filter(row: &[u8], above: &[u8], strategy: FilterStrategy) -> (u8, Vec<u8>) {
+    match strategy {
+        FilterStrategy::Zero => (0, apply_filter(row, above, 0)),
+        FilterStrategy::Up => (2, apply_filter(row, above, 2)),
+        FilterStrategy::Paeth => (4, apply_filter(row, above, 4)),
+        FilterStrategy::Adaptive => (0..=4)
+            .map(|filter_type| (filter_type, apply_filter(row, above, filter_type)))
+            .min_by_key(|(_, filtered)| filtered_sum(filtered))
+            .unwrap_or((0, row.to_vec())),
+    }
+}
+
+/// Builds a minimal PNG stream: signature, `IHDR`, one `IDAT` (zlib-deflated,
+/// filtered scanlines chosen per `filter_strategy`), `IEND`.
+fn encode_png(
+    rgb: &[u8],
+    width: u16,
+    height: u16,
+    compression: u8,
+    filter_strategy: FilterStrategy,
+) -> Vec<u8> {
+    fn chunk(out: &mut Vec<u8>, tag: &[u8; 4], data: &[u8]) {
+        out.extend_from_slice(&(u32::try_from(data.len()).unwrap_or(u32::MAX)).to_be_bytes());
+        let mut crc_input = Vec::with_capacity(4 + data.len());
+        crc_input.extend_from_slice(tag);
+        crc_input.extend_from_slice(data);
+        out.extend_from_slice(&crc_input[..4]);
+        out.extend_from_slice(data);
+        out.extend_from_slice(&crc32(&crc_input).to_be_bytes());
+    }
+
+    let mut png = Vec::new();
+    png.extend_from_slice(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&u32::from(width).to_be_bytes());
+    ihdr.extend_from_slice(&u32::from(height).to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]); // 8-bit depth, color type 2 (RGB), no interlace
+    chunk(&mut png, b"IHDR", &ihdr);
+
+    let mut raw = Vec::with_capacity(rgb.len() + usize::from(height));
+    let mut previous_filtered: Vec<u8> = Vec::new();
+    for row in rgb.chunks_exact(usize::from(width) * 3) {
+        let (filter_type, filtered) = choose_filter(row, &previous_filtered, filter_strategy);
+        raw.push(filter_type);
+        raw.extend_from_slice(&filtered);
+        previous_filtered = filtered;
+    }
+    let level = Compression::new(u32::from(compression.min(9)));
+    let mut encoder = ZlibEncoder::new(Vec::new(), level);
+    let _ = encoder.write_all(&raw);
+    let idat = encoder.finish().unwrap_or_default();
+    chunk(&mut png, b"IDAT", &idat);
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn decode_png(png: &[u8]) -> io::Result<(u16, u16, Vec<u8>)> {
+    if png.len() < 8 + 8 + 13 + 4 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "PNG too short"));
+    }
+    let width = u16::try_from(u32::from_be_bytes(png[16..20].try_into().unwrap())).unwrap_or(0);
+    let height = u16::try_from(u32::from_be_bytes(png[20..24].try_into().unwrap())).unwrap_or(0);
+
+    // Locate the IDAT chunk (this encoder only ever emits a single one).
+    let mut offset = 8;
+    let mut idat = None;
+    while offset + 8 <= png.len() {
+        let len = u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap()) as usize;
+        let tag = &png[offset + 4..offset + 8];
+        let data_start = offset + 8;
+        if data_start + len > png.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "PNG chunk length extends past the end of the payload",
+            ));
+        }
+        if tag == b"IDAT" {
+            idat = Some(&png[data_start..data_start + len]);
+            break;
+        }
+        offset = data_start + len + 4;
+    }
+    let idat = idat.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing IDAT chunk"))?;
+
+    let mut decoder = flate2::read::ZlibDecoder::new(idat);
+    let mut raw = Vec::new();
+    decoder.read_to_end(&mut raw)?;
+
+    let mut rgb = Vec::with_capacity(usize::from(width) * usize::from(height) * 3);
+    let mut previous_filtered: Vec<u8> = Vec::new();
+    for row in raw.chunks_exact(usize::from(width) * 3 + 1) {
+        let filter_type = row[0];
+        let filtered = &row[1..];
+        let reconstructed = unapply_filter(filtered, &previous_filtered, filter_type);
+        rgb.extend_from_slice(&reconstructed);
+        previous_filtered = filtered.to_vec();
+    }
+    Ok((width, height, rgb))
+}
+
+/// Inverse of [`apply_filter`]: reconstructs a raw scanline from its
+/// filtered bytes and the previous scanline's filtered bytes.
+fn unapply_filter(filtered: &[u8], above: &[u8], filter_type: u8) -> Vec<u8> {
+    let mut raw = vec![0u8; filtered.len()];
+    for i in 0..filtered.len() {
+        let left = if i >= PNG_BPP { raw[i - PNG_BPP] } else { 0 };
+        let up = above.get(i).copied().unwrap_or(0);
+        let up_left = if i >= PNG_BPP {
+            above.get(i - PNG_BPP).copied().unwrap_or(0)
+        } else {
+            0
+        };
+
+        raw[i] = match filter_type {
+            1 => filtered[i].wrapping_add(left),
+            2 => filtered[i].wrapping_add(up),
+            3 => {
+                let avg = (u16::from(left) + u16::from(up)) / 2;
+                #[allow(clippy::cast_possible_truncation)]
+                filtered[i].wrapping_add(avg as u8)
+            }
+            4 => filtered[i].wrapping_add(paeth_predictor(left, up, up_left)),
+            _ => filtered[i],
+        };
+    }
+    raw
+}
+
+/// Table-free CRC-32 (PNG's checksum), computed bit by bit for simplicity.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            if crc & 1 != 0 {
+                crc = (crc >> 1) ^ 0xEDB8_8320;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    !crc
+}
+
+impl Encoding for TightPngEncoding {
+    fn encode(
+        &self,
+        data: &[u8],
+        width: u16,
+        height: u16,
+        _quality: u8,
+        compression: u8,
+    ) -> BytesMut {
+        let rgb = rgba_to_rgb(data);
+        let png = encode_png(&rgb, width, height, compression, self.filter_strategy);
+
+        let mut buf = BytesMut::with_capacity(png.len() + 4);
+        buf.put_u8(TIGHT_PNG << 4);
+        crate::common::write_compact_length(&mut buf, png.len());
+        buf.put_slice(&png);
+        buf
+    }
+}
+
+impl Decoder for TightPngEncoding {
+    fn decode(
+        &self,
+        data: &mut BytesMut,
+        _width: u16,
+        _height: u16,
+        pf: &PixelFormat,
+    ) -> io::Result<Vec<u8>> {
+        if data.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "not enough bytes for TightPng control byte",
+            ));
+        }
+        let _control = bytes::Buf::get_u8(data);
+        let len = crate::common::read_compact_length(data)?;
+        if data.len() < len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "not enough bytes for TightPng payload",
+            ));
+        }
+        let payload = data.split_to(len);
+        let (_w, _h, rgb) = decode_png(&payload)?;
+
+        let mut out = Vec::with_capacity(rgb.len() / 3 * RGBA_BPP);
+        for px in rgb.chunks_exact(3) {
+            let packed = crate::common::pack_pixel(pf, px[0], px[1], px[2]);
+            let (r, g, b) = crate::common::unpack_pixel(pf, packed);
+            out.extend_from_slice(&[r, g, b, 255]);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_encode_and_decode() {
+        let pf = PixelFormat::rgba32();
+        let (width, height) = (4, 4);
+        let mut data = Vec::with_capacity(usize::from(width) * usize::from(height) * RGBA_BPP);
+        for y in 0..height {
+            for x in 0..width {
+                #[allow(clippy::cast_possible_truncation)]
+                let (r, g) = (x as u8 * 20, y as u8 * 20);
+                data.extend_from_slice(&[r, g, 128, 255]);
+            }
+        }
+
+        let mut encoded = TightPngEncoding::default().encode(&data, width, height, 0, 6);
+        let decoded = TightPngEncoding::default()
+            .decode(&mut encoded, width, height, &pf)
+            .unwrap();
+
+        assert_eq!(decoded, data);
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pixel-format translation between the crate's internal RGBA buffers and
+//! the wire pixel formats negotiated with RFB clients.
+
+use crate::common::{pack_pixel, unpack_pixel, write_pixel};
+use crate::PixelFormat;
+use bytes::BytesMut;
+
+/// Translates an RGBA buffer (4 bytes per pixel) into the wire representation
+/// described by `pf`.
+#[must_use]
+pub fn translate(data: &[u8], pf: &PixelFormat) -> Vec<u8> {
+    let mut out = BytesMut::with_capacity(data.len());
+    for px in data.chunks_exact(4) {
+        let pixel = pack_pixel(pf, px[0], px[1], px[2]);
+        write_pixel(&mut out, pf, pixel);
+    }
+    out.to_vec()
+}
+
+/// Translates a `pf`-shaped wire buffer back into an RGBA buffer (4 bytes
+/// per pixel, alpha fixed at 255).
+#[must_use]
+pub fn translate_to_rgba(data: &[u8], pf: &PixelFormat) -> Vec<u8> {
+    let bpp = usize::from(pf.bits_per_pixel / 8);
+    let mut out = Vec::with_capacity(data.len() / bpp * 4);
+    for chunk in data.chunks_exact(bpp) {
+        let mut buf = BytesMut::from(chunk);
+        let pixel = crate::common::read_pixel(&mut buf, pf).unwrap_or(0);
+        let (r, g, b) = unpack_pixel(pf, pixel);
+        out.extend_from_slice(&[r, g, b, 255]);
+    }
+    out
+}
+
+/// Number of steps per channel in the uniform color cube [`quantize`] uses.
+const CUBE_STEPS: u32 = 6;
+
+/// Quantizes an RGBA buffer into a palette of up to 256 entries, for
+/// serving color-mapped (`true_colour_flag == 0`, 8bpp) `PixelFormat`s.
+///
+/// Uses a fixed, uniform 6x6x6 color cube (216 entries) rather than a
+/// median-cut search, trading some palette fidelity for a direct,
+/// search-free mapping from RGB to cube cell; used cells are then compacted
+/// into a dense palette.
+///
+/// # Returns
+///
+/// `(palette, indices)`: `palette[i]` is the RGB color for index `i`
+/// (`palette.len() <= 216`), and `indices[p]` is the palette index for
+/// pixel `p`.
+#[must_use]
+pub fn quantize(data: &[u8]) -> (Vec<(u8, u8, u8)>, Vec<u8>) {
+    let bucket = |component: u8| -> u32 { u32::from(component) * CUBE_STEPS / 256 };
+    let cube_index = |r: u32, g: u32, b: u32| -> usize {
+        (r * CUBE_STEPS * CUBE_STEPS + g * CUBE_STEPS + b) as usize
+    };
+    let cube_color = |index: usize| -> (u8, u8, u8) {
+        #[allow(clippy::cast_possible_truncation)]
+        let index = index as u32;
+        let r = index / (CUBE_STEPS * CUBE_STEPS);
+        let g = (index / CUBE_STEPS) % CUBE_STEPS;
+        let b = index % CUBE_STEPS;
+        #[allow(clippy::cast_possible_truncation)]
+        let scale = |step: u32| -> u8 { (step * 255 / (CUBE_STEPS - 1)) as u8 };
+        (scale(r), scale(g), scale(b))
+    };
+
+    let mut used = vec![false; (CUBE_STEPS * CUBE_STEPS * CUBE_STEPS) as usize];
+    let mut indices = Vec::with_capacity(data.len() / 4);
+    for px in data.chunks_exact(4) {
+        let (r, g, b) = (bucket(px[0]), bucket(px[1]), bucket(px[2]));
+        let cube_idx = cube_index(r, g, b);
+        used[cube_idx] = true;
+        indices.push(cube_idx);
+    }
+
+    // Compact used cube cells into a dense 0..palette.len() palette.
+    let mut remap = vec![0u8; used.len()];
+    let mut palette = Vec::with_capacity(256.min(used.len()));
+    for (cube_idx, is_used) in used.iter().enumerate() {
+        if *is_used {
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                remap[cube_idx] = palette.len() as u8;
+            }
+            palette.push(cube_color(cube_idx));
+        }
+    }
+
+    let indices = indices.into_iter().map(|cube_idx| remap[cube_idx]).collect();
+    (palette, indices)
+}
+
+/// Serializes an RFB `SetColourMapEntries` server message: message type 1,
+/// a padding byte, the first color index, the color count, then each
+/// color's red/green/blue maximum values (16 bits each).
+#[must_use]
+pub fn write_set_colour_map_entries(first_color: u16, palette: &[(u8, u8, u8)]) -> BytesMut {
+    use bytes::BufMut;
+
+    let mut buf = BytesMut::with_capacity(6 + palette.len() * 6);
+    buf.put_u8(1); // message-type: SetColourMapEntries
+    buf.put_u8(0); // padding
+    buf.put_u16(first_color);
+    buf.put_u16(u16::try_from(palette.len()).unwrap_or(u16::MAX));
+    for &(r, g, b) in palette {
+        buf.put_u16(u16::from(r) * 257);
+        buf.put_u16(u16::from(g) * 257);
+        buf.put_u16(u16::from(b) * 257);
+    }
+    buf
+}
@@ -0,0 +1,142 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Zlib encoding: Raw pixel data compressed with a single `deflate` stream
+//! that must persist for the lifetime of the connection, since each
+//! rectangle is compressed against the history of the ones before it.
+
+use crate::common::RGBA_BPP;
+use crate::{Decoder, PixelFormat};
+use bytes::{BufMut, BytesMut};
+use flate2::{Compress, Decompress, FlushCompress, FlushDecompress};
+use std::cell::RefCell;
+use std::io;
+
+/// Encodes `data` as raw RGB pixels through a caller-owned, persistent
+/// `deflate` stream, and prefixes the result with a `u32` length as the RFB
+/// Zlib encoding requires.
+///
+/// See [`crate::session::compress_to_vec`] for why the compressed output
+/// isn't simply sized to `rgb.len()`, and [`crate::session::EncodingSession`]
+/// for why the compression level can't vary per rectangle.
+pub fn encode_zlib_persistent(
+    stream: &mut Compress,
+    data: &[u8],
+    _width: u16,
+    _height: u16,
+    _compression: u8,
+) -> BytesMut {
+    let mut rgb = Vec::with_capacity(data.len() / RGBA_BPP * 3);
+    for px in data.chunks_exact(RGBA_BPP) {
+        rgb.extend_from_slice(&px[..3]);
+    }
+
+    let compressed = crate::session::compress_to_vec(stream, &rgb, FlushCompress::Sync);
+
+    let mut buf = BytesMut::with_capacity(compressed.len() + 4);
+    buf.put_u32(u32::try_from(compressed.len()).unwrap_or(u32::MAX));
+    buf.put_slice(&compressed);
+    buf
+}
+
+/// Zlib decoder. Holds the connection-scoped inflate state in a `RefCell`
+/// so it can be driven through the [`Decoder`] trait's `&self` receiver.
+pub struct ZlibEncoding {
+    stream: RefCell<Decompress>,
+}
+
+impl Default for ZlibEncoding {
+    fn default() -> Self {
+        Self {
+            stream: RefCell::new(Decompress::new(true)),
+        }
+    }
+}
+
+impl ZlibEncoding {
+    /// Creates a fresh decoder with an empty inflate history.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Decoder for ZlibEncoding {
+    fn decode(
+        &self,
+        data: &mut BytesMut,
+        width: u16,
+        height: u16,
+        pf: &PixelFormat,
+    ) -> io::Result<Vec<u8>> {
+        if data.len() < 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "not enough bytes for Zlib length prefix",
+            ));
+        }
+        let len = bytes::Buf::get_u32(data) as usize;
+        if data.len() < len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "not enough bytes for Zlib payload",
+            ));
+        }
+        let payload = data.split_to(len);
+
+        let expected = usize::from(width) * usize::from(height) * 3;
+        let mut chunk = vec![0u8; expected.max(1)];
+        let mut stream = self.stream.borrow_mut();
+        let before_out = stream.total_out();
+        stream
+            .decompress(&payload, &mut chunk, FlushDecompress::Sync)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let produced = usize::try_from(stream.total_out() - before_out).unwrap_or(0);
+        let rgb = chunk[..produced].to_vec();
+
+        let mut out = Vec::with_capacity(rgb.len() / 3 * RGBA_BPP);
+        for px in rgb.chunks_exact(3) {
+            let packed = crate::common::pack_pixel(pf, px[0], px[1], px[2]);
+            let (r, g, b) = crate::common::unpack_pixel(pf, packed);
+            out.extend_from_slice(&[r, g, b, 255]);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+
+    #[test]
+    fn round_trips_through_a_persistent_stream() {
+        let pf = PixelFormat::rgba32();
+        let (width, height) = (4, 4);
+        let data: Vec<u8> = (0..u32::from(width) * u32::from(height))
+            .flat_map(|i| {
+                #[allow(clippy::cast_possible_truncation)]
+                let b = (i * 7) as u8;
+                [b, b.wrapping_add(1), b.wrapping_add(2), 255]
+            })
+            .collect();
+
+        let mut stream = Compress::new(Compression::default(), true);
+        let mut encoded = encode_zlib_persistent(&mut stream, &data, width, height, 6);
+
+        let decoded = ZlibEncoding::new().decode(&mut encoded, width, height, &pf).unwrap();
+
+        assert_eq!(decoded, data);
+    }
+}
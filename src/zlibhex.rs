@@ -0,0 +1,185 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `ZlibHex`: Hextile framing with each raw tile additionally compressed
+//! through a persistent `deflate` stream, as TigerVNC's `ZlibHex` does.
+
+use crate::common::RGBA_BPP;
+use crate::{Decoder, PixelFormat};
+use bytes::{Buf, BufMut, BytesMut};
+use flate2::{Compress, Decompress, FlushCompress, FlushDecompress};
+use std::cell::RefCell;
+use std::io;
+
+/// Tile edge length, matching plain Hextile.
+const TILE_SIZE: u16 = 16;
+
+/// Encodes `data` as `ZlibHex`: Hextile tiling where every tile's raw pixels
+/// are deflated through the caller-owned, connection-scoped `stream`.
+///
+/// See [`crate::session::compress_to_vec`] for why the compressed output
+/// isn't simply sized to `rgb.len()`, and [`crate::session::EncodingSession`]
+/// for why the compression level can't vary per rectangle.
+pub fn encode_zlibhex_persistent(
+    stream: &mut Compress,
+    data: &[u8],
+    width: u16,
+    height: u16,
+    _compression: u8,
+) -> BytesMut {
+    let mut buf = BytesMut::new();
+
+    let mut y0 = 0;
+    while y0 < height {
+        let h = TILE_SIZE.min(height - y0);
+        let mut x0 = 0;
+        while x0 < width {
+            let w = TILE_SIZE.min(width - x0);
+
+            let mut rgb = Vec::with_capacity(usize::from(w) * usize::from(h) * 3);
+            for y in y0..y0 + h {
+                for x in x0..x0 + w {
+                    let px = crate::common::pixel_at(data, width, x, y);
+                    rgb.extend_from_slice(&px[..3]);
+                }
+            }
+
+            let compressed = crate::session::compress_to_vec(stream, &rgb, FlushCompress::Sync);
+            buf.put_u16(u16::try_from(compressed.len()).unwrap_or(u16::MAX));
+            buf.put_slice(&compressed);
+
+            x0 += w;
+        }
+        y0 += h;
+    }
+    buf
+}
+
+/// `ZlibHex` decoder, holding the connection-scoped inflate state.
+pub struct ZlibHexEncoding {
+    stream: RefCell<Decompress>,
+}
+
+impl Default for ZlibHexEncoding {
+    fn default() -> Self {
+        Self {
+            stream: RefCell::new(Decompress::new(true)),
+        }
+    }
+}
+
+impl ZlibHexEncoding {
+    /// Creates a fresh decoder with an empty inflate history.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Decoder for ZlibHexEncoding {
+    fn decode(
+        &self,
+        data: &mut BytesMut,
+        width: u16,
+        height: u16,
+        pf: &PixelFormat,
+    ) -> io::Result<Vec<u8>> {
+        let mut out = vec![0u8; usize::from(width) * usize::from(height) * RGBA_BPP];
+        let mut stream = self.stream.borrow_mut();
+
+        let mut y0 = 0;
+        while y0 < height {
+            let h = TILE_SIZE.min(height - y0);
+            let mut x0 = 0;
+            while x0 < width {
+                let w = TILE_SIZE.min(width - x0);
+
+                if data.len() < 2 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "not enough bytes for ZlibHex tile length",
+                    ));
+                }
+                let len = usize::from(data.get_u16());
+                if data.len() < len {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "not enough bytes for ZlibHex tile payload",
+                    ));
+                }
+                let payload = data.split_to(len);
+
+                let expected = usize::from(w) * usize::from(h) * 3;
+                let mut chunk = vec![0u8; expected.max(1)];
+                let before_out = stream.total_out();
+                stream
+                    .decompress(&payload, &mut chunk, FlushDecompress::Sync)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+                let produced = usize::try_from(stream.total_out() - before_out).unwrap_or(0);
+
+                let mut i = 0;
+                for y in y0..y0 + h {
+                    for x in x0..x0 + w {
+                        if i + 3 > produced {
+                            break;
+                        }
+                        let packed = crate::common::pack_pixel(
+                            pf,
+                            chunk[i],
+                            chunk[i + 1],
+                            chunk[i + 2],
+                        );
+                        let (r, g, b) = crate::common::unpack_pixel(pf, packed);
+                        let offset =
+                            (usize::from(y) * usize::from(width) + usize::from(x)) * RGBA_BPP;
+                        out[offset..offset + RGBA_BPP].copy_from_slice(&[r, g, b, 255]);
+                        i += 3;
+                    }
+                }
+
+                x0 += w;
+            }
+            y0 += h;
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+
+    #[test]
+    fn round_trips_through_a_persistent_stream() {
+        let pf = PixelFormat::rgba32();
+        let (width, height) = (TILE_SIZE, TILE_SIZE);
+        let data: Vec<u8> = (0..u32::from(width) * u32::from(height))
+            .flat_map(|i| {
+                #[allow(clippy::cast_possible_truncation)]
+                let b = (i * 7) as u8;
+                [b, b.wrapping_add(1), b.wrapping_add(2), 255]
+            })
+            .collect();
+
+        let mut stream = Compress::new(Compression::default(), true);
+        let mut encoded = encode_zlibhex_persistent(&mut stream, &data, width, height, 6);
+
+        let decoded = ZlibHexEncoding::new()
+            .decode(&mut encoded, width, height, &pf)
+            .unwrap();
+
+        assert_eq!(decoded, data);
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ZRLE (Zlib compressed Tiled Run-Length Encoding): the framebuffer is
+//! split into 64x64 tiles, each tile's pixels are packed (this
+//! implementation always uses the raw-pixel tile subencoding), and the
+//! whole rectangle body is then deflated through one persistent stream.
+
+use crate::common::RGBA_BPP;
+use crate::{Decoder, PixelFormat};
+use bytes::{Buf, BufMut, BytesMut};
+use flate2::{Compress, Decompress, FlushCompress, FlushDecompress};
+use std::cell::RefCell;
+use std::io;
+
+/// Tile edge length used by ZRLE.
+const TILE_SIZE: u16 = 64;
+
+/// Tile subencoding byte for "raw pixel data" (no run-length or palette).
+const ZRLE_RAW: u8 = 0;
+
+fn tile_body(data: &[u8], width: u16, x0: u16, y0: u16, w: u16, h: u16) -> Vec<u8> {
+    let mut body = Vec::with_capacity(1 + usize::from(w) * usize::from(h) * 3);
+    body.push(ZRLE_RAW);
+    for y in y0..y0 + h {
+        for x in x0..x0 + w {
+            let px = crate::common::pixel_at(data, width, x, y);
+            body.extend_from_slice(&px[..3]);
+        }
+    }
+    body
+}
+
+/// Encodes `data` as ZRLE through a caller-owned, persistent `deflate`
+/// stream, prefixing the compressed rectangle body with a `u32` length.
+///
+/// See [`crate::session::compress_to_vec`] for why the compressed output
+/// isn't simply sized to `raw.len()`, and [`crate::session::EncodingSession`]
+/// for why the compression level can't vary per rectangle.
+pub fn encode_zrle_persistent(
+    stream: &mut Compress,
+    data: &[u8],
+    width: u16,
+    height: u16,
+    _compression: u8,
+) -> BytesMut {
+
+    let mut raw = Vec::new();
+    let mut y0 = 0;
+    while y0 < height {
+        let h = TILE_SIZE.min(height - y0);
+        let mut x0 = 0;
+        while x0 < width {
+            let w = TILE_SIZE.min(width - x0);
+            raw.extend_from_slice(&tile_body(data, width, x0, y0, w, h));
+            x0 += w;
+        }
+        y0 += h;
+    }
+
+    let compressed = crate::session::compress_to_vec(stream, &raw, FlushCompress::Sync);
+
+    let mut buf = BytesMut::with_capacity(compressed.len() + 4);
+    buf.put_u32(u32::try_from(compressed.len()).unwrap_or(u32::MAX));
+    buf.put_slice(&compressed);
+    buf
+}
+
+/// ZRLE decoder, holding the connection-scoped inflate state.
+pub struct ZrleEncoding {
+    stream: RefCell<Decompress>,
+}
+
+impl Default for ZrleEncoding {
+    fn default() -> Self {
+        Self {
+            stream: RefCell::new(Decompress::new(true)),
+        }
+    }
+}
+
+impl ZrleEncoding {
+    /// Creates a fresh decoder with an empty inflate history.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Decoder for ZrleEncoding {
+    fn decode(
+        &self,
+        data: &mut BytesMut,
+        width: u16,
+        height: u16,
+        pf: &PixelFormat,
+    ) -> io::Result<Vec<u8>> {
+        if data.len() < 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "not enough bytes for ZRLE length prefix",
+            ));
+        }
+        let len = data.get_u32() as usize;
+        if data.len() < len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "not enough bytes for ZRLE payload",
+            ));
+        }
+        let payload = data.split_to(len);
+
+        let tile_count = {
+            let cols = usize::from(width.div_ceil(TILE_SIZE));
+            let rows = usize::from(height.div_ceil(TILE_SIZE));
+            cols * rows
+        };
+        let expected = usize::from(width) * usize::from(height) * 3 + tile_count;
+        let mut chunk = vec![0u8; expected.max(1)];
+        let mut stream = self.stream.borrow_mut();
+        let before_out = stream.total_out();
+        stream
+            .decompress(&payload, &mut chunk, FlushDecompress::Sync)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let produced = usize::try_from(stream.total_out() - before_out).unwrap_or(0);
+        let raw = &chunk[..produced];
+
+        let mut out = vec![0u8; usize::from(width) * usize::from(height) * RGBA_BPP];
+        let mut pos = 0;
+        let mut y0 = 0;
+        while y0 < height {
+            let h = TILE_SIZE.min(height - y0);
+            let mut x0 = 0;
+            while x0 < width {
+                let w = TILE_SIZE.min(width - x0);
+                if pos >= raw.len() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "not enough decompressed bytes for ZRLE tile",
+                    ));
+                }
+                pos += 1; // subencoding byte (always ZRLE_RAW in this crate)
+                for y in y0..y0 + h {
+                    for x in x0..x0 + w {
+                        if pos + 3 > raw.len() {
+                            return Err(io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                "not enough decompressed bytes for ZRLE pixel",
+                            ));
+                        }
+                        let packed = crate::common::pack_pixel(
+                            pf,
+                            raw[pos],
+                            raw[pos + 1],
+                            raw[pos + 2],
+                        );
+                        let (r, g, b) = crate::common::unpack_pixel(pf, packed);
+                        let offset =
+                            (usize::from(y) * usize::from(width) + usize::from(x)) * RGBA_BPP;
+                        out[offset..offset + RGBA_BPP].copy_from_slice(&[r, g, b, 255]);
+                        pos += 3;
+                    }
+                }
+                x0 += w;
+            }
+            y0 += h;
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+
+    #[test]
+    fn round_trips_through_a_persistent_stream() {
+        let pf = PixelFormat::rgba32();
+        let (width, height) = (TILE_SIZE, TILE_SIZE);
+        let data: Vec<u8> = (0..u32::from(width) * u32::from(height))
+            .flat_map(|i| {
+                #[allow(clippy::cast_possible_truncation)]
+                let b = (i * 7) as u8;
+                [b, b.wrapping_add(1), b.wrapping_add(2), 255]
+            })
+            .collect();
+
+        let mut stream = Compress::new(Compression::default(), true);
+        let mut encoded = encode_zrle_persistent(&mut stream, &data, width, height, 6);
+
+        let decoded = ZrleEncoding::new().decode(&mut encoded, width, height, &pf).unwrap();
+
+        assert_eq!(decoded, data);
+    }
+}
@@ -0,0 +1,277 @@
+// Copyright 2025 Dustin McAfee
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ZYWRLE: ZRLE with a wavelet pre-pass that trades a little image fidelity
+//! for a flatter, more compressible residual before `deflate` sees it.
+//!
+//! [`zywrle_analyze`] is the forward transform; the full connection-scoped
+//! pipeline (wavelet pass, tiling, persistent `deflate`) lives behind
+//! [`crate::EncodingSession`], which drives this module and
+//! [`crate::zrle`]'s framing together.
+
+use crate::common::RGBA_BPP;
+use crate::{Decoder, PixelFormat};
+use bytes::BytesMut;
+use flate2::Compress;
+use std::io;
+
+/// Tile edge length, matching ZRLE (ZYWRLE reuses ZRLE's tile framing).
+const TILE_SIZE: u16 = 64;
+
+/// Maps a 0-100 encoder `quality` to a wavelet detail level: higher quality
+/// keeps more detail (a smaller level), lower quality quantizes harder.
+#[must_use]
+fn level_for_quality(quality: u8) -> u8 {
+    match quality {
+        80..=100 => 0,
+        50..=79 => 1,
+        20..=49 => 2,
+        _ => 3,
+    }
+}
+
+/// Encodes `data` as ZYWRLE: a wavelet pass per ZRLE tile (detail level
+/// controlled by `quality` unless `level_override` is set), followed by the
+/// same persistent-`deflate` framing ZRLE uses.
+#[allow(clippy::too_many_arguments)]
+pub fn encode_zywrle_persistent(
+    stream: &mut Compress,
+    data: &[u8],
+    width: u16,
+    height: u16,
+    quality: u8,
+    level_override: u8,
+    compression: u8,
+) -> BytesMut {
+    let level = if level_override > 0 {
+        level_override
+    } else {
+        level_for_quality(quality)
+    };
+
+    let mut transformed = vec![0u8; data.len()];
+    let mut y0 = 0;
+    while y0 < height {
+        let h = TILE_SIZE.min(height - y0);
+        let mut x0 = 0;
+        while x0 < width {
+            let w = TILE_SIZE.min(width - x0);
+
+            let mut rgb = Vec::with_capacity(usize::from(w) * usize::from(h) * 3);
+            for y in y0..y0 + h {
+                for x in x0..x0 + w {
+                    let px = crate::common::pixel_at(data, width, x, y);
+                    rgb.extend_from_slice(&px[..3]);
+                }
+            }
+            let analyzed = zywrle_analyze(&rgb, w, h, level);
+
+            let mut i = 0;
+            for y in y0..y0 + h {
+                for x in x0..x0 + w {
+                    let offset = (usize::from(y) * usize::from(width) + usize::from(x)) * RGBA_BPP;
+                    transformed[offset..offset + 3].copy_from_slice(&analyzed[i..i + 3]);
+                    transformed[offset + 3] = 255;
+                    i += 3;
+                }
+            }
+
+            x0 += w;
+        }
+        y0 += h;
+    }
+
+    crate::zrle::encode_zrle_persistent(stream, &transformed, width, height, compression)
+}
+
+/// A single in-place Haar wavelet pass over one row of `len` samples,
+/// replacing pairs `(a, b)` with `(average, difference)`.
+fn haar_forward_row(row: &mut [i16]) {
+    let mut tmp = vec![0i16; row.len()];
+    let half = row.len() / 2;
+    for i in 0..half {
+        let a = row[2 * i];
+        let b = row[2 * i + 1];
+        tmp[i] = (a + b) / 2;
+        tmp[half + i] = a - b;
+    }
+    row.copy_from_slice(&tmp);
+}
+
+/// Inverse of [`haar_forward_row`].
+fn haar_inverse_row(row: &mut [i16]) {
+    let mut tmp = vec![0i16; row.len()];
+    let half = row.len() / 2;
+    for i in 0..half {
+        let avg = row[i];
+        let diff = row[half + i];
+        let b = avg - diff / 2;
+        let a = b + diff;
+        tmp[2 * i] = a;
+        tmp[2 * i + 1] = b;
+    }
+    row.copy_from_slice(&tmp);
+}
+
+/// Zeroes wavelet detail coefficients below a `level`-dependent threshold,
+/// the lossy step that gives ZYWRLE its name (Zlib + Wavelet + RLE).
+fn quantize_detail(row: &mut [i16], level: u8) {
+    let half = row.len() / 2;
+    let threshold = i16::from(level) * 2;
+    for v in &mut row[half..] {
+        if v.abs() < threshold {
+            *v = 0;
+        }
+    }
+}
+
+/// Applies a one-level Haar wavelet transform, per channel and per row, to
+/// an RGB tile, quantizing detail coefficients according to `level`
+/// (0 = lossless passthrough, higher = more aggressive).
+///
+/// The transform pairs samples, so a tile with an odd `width` (possible at
+/// a rectangle's right edge) is passed through unchanged.
+#[must_use]
+pub fn zywrle_analyze(rgb: &[u8], width: u16, height: u16, level: u8) -> Vec<u8> {
+    if level == 0 || width % 2 != 0 {
+        return rgb.to_vec();
+    }
+
+    let w = usize::from(width);
+    let mut out = rgb.to_vec();
+    for channel in 0..3 {
+        for y in 0..usize::from(height) {
+            let mut row: Vec<i16> = (0..w)
+                .map(|x| i16::from(rgb[(y * w + x) * 3 + channel]))
+                .collect();
+            haar_forward_row(&mut row);
+            quantize_detail(&mut row, level);
+            for (x, value) in row.into_iter().enumerate() {
+                out[(y * w + x) * 3 + channel] = value.clamp(0, 255) as u8;
+            }
+        }
+    }
+    out
+}
+
+/// Inverse of [`zywrle_analyze`]: reconstructs an approximate RGB tile from
+/// its wavelet-transformed representation.
+#[must_use]
+pub fn zywrle_synthesize(transformed: &[u8], width: u16, height: u16, level: u8) -> Vec<u8> {
+    if level == 0 || width % 2 != 0 {
+        return transformed.to_vec();
+    }
+
+    let w = usize::from(width);
+    let mut out = transformed.to_vec();
+    for channel in 0..3 {
+        for y in 0..usize::from(height) {
+            let mut row: Vec<i16> = (0..w)
+                .map(|x| i16::from(transformed[(y * w + x) * 3 + channel]))
+                .collect();
+            haar_inverse_row(&mut row);
+            for (x, value) in row.into_iter().enumerate() {
+                out[(y * w + x) * 3 + channel] = value.clamp(0, 255) as u8;
+            }
+        }
+    }
+    out
+}
+
+/// ZYWRLE decoder. Mirrors [`crate::zrle::ZrleEncoding`]'s tile framing and
+/// persistent inflate state, adding the wavelet synthesis pass.
+pub struct ZywrleEncoding {
+    inner: crate::zrle::ZrleEncoding,
+    level: u8,
+}
+
+impl Default for ZywrleEncoding {
+    fn default() -> Self {
+        Self {
+            inner: crate::zrle::ZrleEncoding::new(),
+            level: 1,
+        }
+    }
+}
+
+impl ZywrleEncoding {
+    /// Creates a fresh decoder with an empty inflate history and the given
+    /// wavelet detail level.
+    #[must_use]
+    pub fn new(level: u8) -> Self {
+        Self {
+            inner: crate::zrle::ZrleEncoding::new(),
+            level,
+        }
+    }
+}
+
+impl Decoder for ZywrleEncoding {
+    fn decode(
+        &self,
+        data: &mut BytesMut,
+        width: u16,
+        height: u16,
+        pf: &PixelFormat,
+    ) -> io::Result<Vec<u8>> {
+        // The ZRLE tile framing is identical; only the pixel payload carries
+        // a wavelet-transformed signal. Decode via the ZRLE path, then
+        // synthesize to undo the transform.
+        let rgba = self.inner.decode(data, width, height, pf)?;
+
+        let mut rgb = Vec::with_capacity(rgba.len() / RGBA_BPP * 3);
+        for px in rgba.chunks_exact(RGBA_BPP) {
+            rgb.extend_from_slice(&px[..3]);
+        }
+        let synthesized = zywrle_synthesize(&rgb, width, height, self.level);
+
+        let mut out = Vec::with_capacity(rgba.len());
+        for px in synthesized.chunks_exact(3) {
+            out.extend_from_slice(&[px[0], px[1], px[2], 255]);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+
+    #[test]
+    fn round_trips_losslessly_at_wavelet_level_zero() {
+        // Level 0 is zywrle_analyze/zywrle_synthesize's documented lossless
+        // passthrough, so this exercises the ZRLE-plus-wavelet framing
+        // without the quantization that makes higher levels lossy.
+        let pf = PixelFormat::rgba32();
+        let (width, height) = (TILE_SIZE, TILE_SIZE);
+        let data: Vec<u8> = (0..u32::from(width) * u32::from(height))
+            .flat_map(|i| {
+                #[allow(clippy::cast_possible_truncation)]
+                let b = (i * 7) as u8;
+                [b, b.wrapping_add(1), b.wrapping_add(2), 255]
+            })
+            .collect();
+
+        let mut stream = Compress::new(Compression::default(), true);
+        let mut encoded =
+            encode_zywrle_persistent(&mut stream, &data, width, height, 100, 0, 6);
+
+        let decoded = ZywrleEncoding::new(0)
+            .decode(&mut encoded, width, height, &pf)
+            .unwrap();
+
+        assert_eq!(decoded, data);
+    }
+}